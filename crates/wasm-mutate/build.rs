@@ -0,0 +1,110 @@
+//! Expands `operators.in` into the arity-by-result-type `Operator` match
+//! arms consumed by `DFGIcator::get_dfg` via `include!`.
+//!
+//! `get_dfg` used to hand-write one match arm per operator, each a
+//! copy-pasted block that pops its operands, asserts they're distinct,
+//! pushes a `StackType::IndexAtCode(idx, N)` node over them and fixes up
+//! `parents`. Encoding an operator's pop-count and result type once in
+//! `operators.in` instead keeps the generated arity in `IndexAtCode` from
+//! drifting out of sync with the number of `pop_operand` calls, and makes
+//! adding a new operator a one-line spec edit.
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// One line of `operators.in`: a bare (payload-free) `Operator` variant
+/// name, how many operands it pops off the stack, and the
+/// `PrimitiveTypeInfo` variant it pushes back.
+struct OperatorSpec {
+    variant: String,
+    arity: u32,
+    result: String,
+}
+
+fn parse_operators_in(source: &str) -> Vec<OperatorSpec> {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut fields = line.split_whitespace();
+            let variant = fields
+                .next()
+                .unwrap_or_else(|| panic!("operators.in: missing operator name in {:?}", line))
+                .to_string();
+            let arity: u32 = fields
+                .next()
+                .unwrap_or_else(|| panic!("operators.in: missing arity for {}", variant))
+                .parse()
+                .unwrap_or_else(|e| panic!("operators.in: bad arity for {}: {}", variant, e));
+            let result = fields
+                .next()
+                .unwrap_or_else(|| panic!("operators.in: missing result type for {}", variant))
+                .to_string();
+            OperatorSpec {
+                variant,
+                arity,
+                result,
+            }
+        })
+        .collect()
+}
+
+/// Appends the `Operator::<variant> => { ... }` match arm for one spec
+/// entry to `out`. Binary arms pop their left operand before their right
+/// one but record them in reverse (`[right, left]`), matching every
+/// hand-written binary arm in `get_dfg`.
+fn emit_arm(spec: &OperatorSpec, out: &mut String) {
+    let result = format!("PrimitiveTypeInfo::{}", spec.result);
+    match spec.arity {
+        1 => write!(
+            out,
+            "Operator::{variant} => {{\
+             \n    let operand = DFGIcator::pop_operand(&mut stack, &mut dfg_map, idx, &mut operatormap, &mut parents, false);\
+             \n    let idx = DFGIcator::push_node(StackType::IndexAtCode(idx, 1), idx, &mut dfg_map, &mut operatormap, &mut stack, vec![operand], &mut parents, color, {result});\
+             \n    parents[operand] = idx as i32;\
+             \n}}\n",
+            variant = spec.variant,
+            result = result,
+        )
+        .unwrap(),
+        2 => write!(
+            out,
+            "Operator::{variant} => {{\
+             \n    let leftidx = DFGIcator::pop_operand(&mut stack, &mut dfg_map, idx, &mut operatormap, &mut parents, false);\
+             \n    let rightidx = DFGIcator::pop_operand(&mut stack, &mut dfg_map, idx, &mut operatormap, &mut parents, false);\
+             \n    assert_ne!(leftidx, rightidx);\
+             \n    let idx = DFGIcator::push_node(StackType::IndexAtCode(idx, 2), idx, &mut dfg_map, &mut operatormap, &mut stack, vec![rightidx, leftidx], &mut parents, color, {result});\
+             \n    parents[leftidx] = idx as i32;\
+             \n    parents[rightidx] = idx as i32;\
+             \n}}\n",
+            variant = spec.variant,
+            result = result,
+        )
+        .unwrap(),
+        other => panic!(
+            "operators.in: unsupported arity {} for {}",
+            other, spec.variant
+        ),
+    }
+}
+
+fn main() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let spec_path = Path::new(&manifest_dir).join("operators.in");
+    println!("cargo:rerun-if-changed={}", spec_path.display());
+
+    let source = fs::read_to_string(&spec_path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {}", spec_path.display(), e));
+
+    let mut generated = String::new();
+    for spec in &parse_operators_in(&source) {
+        emit_arm(spec, &mut generated);
+    }
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let dest_path = Path::new(&out_dir).join("operators_generated.rs");
+    fs::write(&dest_path, generated).unwrap();
+}
@@ -1,14 +1,130 @@
+//! `DFGIcator`/`MiniDFG` construction depends only on `alloc` (the crate
+//! root is expected to bring it in with `extern crate alloc;`), so this
+//! module can be embedded in `no_std` fuzzing/analysis hosts. `HashMap`
+//! falls back to `alloc`'s `BTreeMap` without the `std` feature, since
+//! `std::collections::HashMap` itself isn't available without `std`. The
+//! ANSI-colored [`MiniDFG::pretty_print`] and `Display` impl are opt-in
+//! behind `std`, since they exist for human debugging rather than the
+//! mutator's own data flow.
+
+#[cfg(feature = "std")]
 use std::collections::HashMap;
 
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as HashMap;
+#[cfg(not(feature = "std"))]
+use alloc::{format, string::String, vec, vec::Vec};
+
 use wasmparser::{Operator, Range};
 
 use crate::{module::PrimitiveTypeInfo, ModuleInfo};
 
 use super::OperatorAndByteOffset;
 
+/// A basic block in the function's control-flow graph, with its predecessor
+/// and successor block indexes.
+///
+/// Successor/predecessor edges come from fall-through (the next block in
+/// program order) and from the explicit branch targets of `Br`, `BrIf`,
+/// `BrTable`, `If` and `Else`, resolved through the block-nesting stack while
+/// the CFG is built.
+#[derive(Debug, Clone, Default)]
+pub struct CfgBlock {
+    /// Operator range this block spans.
+    pub range: Range,
+    /// Indexes (into `ControlFlowGraph::blocks`) of the blocks this one can fall or jump to.
+    pub succs: Vec<usize>,
+    /// Indexes of the blocks that can fall or jump into this one.
+    pub preds: Vec<usize>,
+}
+
+/// A function's control-flow graph over its `OperatorAndByteOffset` stream,
+/// together with reverse-postorder numbering and immediate dominators.
+#[derive(Debug, Clone, Default)]
+pub struct ControlFlowGraph {
+    /// All basic blocks, indexed by block id.
+    pub blocks: Vec<CfgBlock>,
+    /// Reverse-postorder position of each block, indexed by block id.
+    pub rpo: Vec<usize>,
+    /// Immediate dominator of each block, indexed by block id. `idom[entry] == entry`.
+    pub idom: Vec<usize>,
+    /// `false` if the block nesting of the operator stream couldn't be
+    /// resolved while building the graph: an `if`/`block` with no matching
+    /// `end`, an `end` with no open construct, or a branch past the outermost
+    /// label. Back-edge dominance is not checked. When `false` the dominator
+    /// information above should not be trusted and callers should fall back
+    /// to the conservative single-basic-block behavior.
+    pub reducible: bool,
+}
+
+impl ControlFlowGraph {
+    /// Returns `true` if `a` dominates `b` (every path from the entry block to
+    /// `b` passes through `a`). A block always dominates itself.
+    pub fn dominates(&self, a: usize, b: usize) -> bool {
+        let mut current = b;
+        loop {
+            if current == a {
+                return true;
+            }
+            if current == self.idom[current] {
+                // Reached the entry block without finding `a`.
+                return current == a;
+            }
+            current = self.idom[current];
+        }
+    }
+}
+
+/// Reusable scratch storage for a single `get_dfg` call's buffers.
+///
+/// A fuzzing/mutation campaign calls `get_dfg` once per candidate
+/// instruction, which would otherwise freshly heap-allocate a `Vec`/
+/// `HashMap` for every candidate. `DFGIcator` keeps one of these around and
+/// `reset`s it (clearing, not dropping, each buffer) between calls so the
+/// allocation is paid for once and then amortized over the whole campaign.
+#[derive(Debug, Default)]
+struct DfgScratch {
+    dfg_map: Vec<StackEntry>,
+    operatormap: HashMap<usize, usize>,
+    stack: Vec<usize>,
+    parents: Vec<i32>,
+    fingerprints: HashMap<u128, Vec<usize>>,
+}
+
+impl DfgScratch {
+    fn with_capacity(capacity: usize) -> Self {
+        DfgScratch {
+            dfg_map: Vec::with_capacity(capacity),
+            // The `BTreeMap` fallback has no `with_capacity`.
+            #[cfg(feature = "std")]
+            operatormap: HashMap::with_capacity(capacity),
+            #[cfg(not(feature = "std"))]
+            operatormap: HashMap::new(),
+            stack: Vec::with_capacity(capacity),
+            parents: Vec::with_capacity(capacity),
+            #[cfg(feature = "std")]
+            fingerprints: HashMap::with_capacity(capacity),
+            #[cfg(not(feature = "std"))]
+            fingerprints: HashMap::new(),
+        }
+    }
+
+    /// Rewinds every buffer back to empty, retaining their capacity.
+    fn reset(&mut self) {
+        self.dfg_map.clear();
+        self.operatormap.clear();
+        self.stack.clear();
+        self.parents.clear();
+        self.fingerprints.clear();
+    }
+}
+
 /// It executes a minimal symbolic evaluation of the stack to detect operands location in the code for certain operators
 /// For example, i.add operator should know who are its operands
-pub struct DFGIcator {}
+pub struct DFGIcator {
+    /// Scratch buffers reused (cleared, not freed) across `get_dfg` calls.
+    scratch: DfgScratch,
+}
 
 #[derive(Debug)]
 pub struct BBlock {
@@ -30,6 +146,29 @@ pub struct StackEntry {
     pub color: u32,
     /// Instruction index if its apply
     pub operator_idx: usize,
+    /// `true` if evaluating this entry has no observable side effect and
+    /// always produces the same value given the same operands (constants,
+    /// `local.get`, `global.get` and pure arithmetic). Calls, loads, stores
+    /// and all local/global set/tee variants are impure.
+    pub is_pure: bool,
+    /// Number of other entries in the same `MiniDFG` that reference this one
+    /// as an operand. Filled in once the whole DFG has been built, since it
+    /// depends on every entry that comes after this one.
+    pub use_count: u32,
+    /// `true` if this entry may be collapsed into a single tree expression
+    /// and freely duplicated by the peephole egraph, i.e. it is pure and used
+    /// at most once. A non-inlinable node must stay anchored (spilled to a
+    /// local at its definition, referenced by `LocalGet` at each use) so
+    /// rewrites cannot reorder side effects or recompute an expensive value.
+    pub inlinable: bool,
+    /// A content-addressed, 128-bit hash of this entry's computation: the
+    /// operator's identity and immediate payload folded together with every
+    /// operand's own fingerprint, in operand order. Two `inlinable` entries
+    /// with equal fingerprints and return types compute the same value, which
+    /// a GVN/CSE-style mutation can use to rewrite a later redundant
+    /// computation into a reference to an earlier one. Filled in once the
+    /// whole DFG has been built, in the same pass as `use_count`.
+    pub fingerprint: u128,
 }
 
 /// This is the IR used to turn wasm to eterm and back
@@ -53,8 +192,49 @@ pub enum StackType {
         align: u8,
         memory: u32,
     },
+    Store {
+        offset: u64,
+        align: u8,
+        memory: u32,
+    },
     Undef,
     IndexAtCode(usize, usize),
+    /// A merge point for values that reach a basic block with more than one
+    /// incoming definition (e.g. the join after an `if`/`else`, or a loop
+    /// header's carried value). `operands` holds one definition per
+    /// predecessor block, in `ControlFlowGraph::blocks[block].preds` order.
+    /// Only `get_function_dfg` produces these; `get_dfg`'s single-basic-block
+    /// view never needs to merge anything.
+    Phi,
+}
+
+impl StackType {
+    /// `true` if evaluating this node has no observable side effect and
+    /// always returns the same value for the same operands. `Call`, `Load`,
+    /// `Store` and all local/global set/tee variants are impure; `Undef` is
+    /// conservatively treated as impure since its origin is unknown, and
+    /// `Phi` is impure since which predecessor's value it yields depends on
+    /// the path taken to reach it.
+    pub fn is_pure(&self) -> bool {
+        match self {
+            StackType::I32(_)
+            | StackType::I64(_)
+            | StackType::LocalGet(_)
+            | StackType::GlobalGet(_)
+            // Pure arithmetic/comparison/conversion operators, all of which
+            // are destackified as `IndexAtCode`.
+            | StackType::IndexAtCode(..) => true,
+            StackType::LocalSet(_)
+            | StackType::LocalTee(_)
+            | StackType::GlobalSet(_)
+            | StackType::Drop
+            | StackType::Call { .. }
+            | StackType::Load { .. }
+            | StackType::Store { .. }
+            | StackType::Undef
+            | StackType::Phi => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -71,6 +251,18 @@ pub struct MiniDFG {
     // the ith instruction's parent instruction
     // We write each stack entry having no parent, i.e. a root in the dfg
     pub parents: Vec<i32>,
+    /// Maps a `StackEntry::fingerprint` to every entry index sharing it.
+    /// Entries that land in the same bucket, are both `inlinable` and have
+    /// matching `return_type`s compute the same value, and a GVN mutation can
+    /// rewrite a later one into a reference to an earlier one.
+    pub fingerprints: HashMap<u128, Vec<usize>>,
+    /// For each control-flow-graph block (indexed as in
+    /// `ControlFlowGraph::blocks`), the entry indexes live on the stack when
+    /// the block starts, i.e. the values it inherits from its
+    /// predecessor(s). Empty for a `MiniDFG` built by `get_dfg`, which only
+    /// ever sees a single basic block in isolation; populated by
+    /// `get_function_dfg`.
+    pub block_roots: Vec<Vec<usize>>,
 }
 
 impl MiniDFG {
@@ -84,6 +276,15 @@ impl MiniDFG {
         loop {
             match worklist.pop() {
                 Some(entry) => {
+                    // Collapsing this subtree into a single tree expression
+                    // would duplicate any non-`inlinable` descendant wherever
+                    // its subtree is referenced again, which could reorder or
+                    // recompute a side effect. Only the root itself is exempt,
+                    // since it is not duplicated by this check.
+                    if entry.entry_idx != current && !entry.inlinable {
+                        return false;
+                    }
+
                     colors.push(entry.color);
 
                     entry.operands.iter().for_each(|i| {
@@ -111,9 +312,304 @@ impl MiniDFG {
     }
 }
 
+/// A large odd 64-bit constant used to mix fingerprint halves; any prime
+/// works, this is the FNV-1a 64-bit prime.
+const FINGERPRINT_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A minimal FNV-1a `Hasher`, used in place of
+/// `std::collections::hash_map::DefaultHasher` so `seed_fingerprint` stays on
+/// the `alloc`-only construction path; `DefaultHasher` itself is `std`-only.
+struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        // FNV offset basis.
+        FnvHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl core::hash::Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(FINGERPRINT_PRIME);
+        }
+    }
+}
+
+/// Computes the seed fingerprint of a single entry from its operator's
+/// identity (ignoring position, so identical operators at different code
+/// offsets produce the same seed) and immediate payload. Operand
+/// fingerprints are folded in separately by the caller.
+fn seed_fingerprint(entry: &StackEntry, operators: &[OperatorAndByteOffset]) -> u128 {
+    use core::hash::{Hash, Hasher};
+
+    let mut hasher = FnvHasher::default();
+    match &entry.operator {
+        // `IndexAtCode` erases the concrete opcode, so recover it from the
+        // operator stream to avoid unifying e.g. `i32.add` with `i32.sub`.
+        StackType::IndexAtCode(operator_idx, _) => {
+            core::mem::discriminant(&operators[*operator_idx].0).hash(&mut hasher);
+        }
+        other => core::mem::discriminant(other).hash(&mut hasher),
+    }
+    let tag = hasher.finish();
+
+    let payload: u64 = match &entry.operator {
+        StackType::I32(v) => *v as u32 as u64,
+        StackType::I64(v) => *v as u64,
+        StackType::LocalGet(i) | StackType::LocalSet(i) | StackType::LocalTee(i) => *i as u64,
+        StackType::GlobalGet(i) | StackType::GlobalSet(i) => *i as u64,
+        StackType::Call { function_index, .. } => *function_index as u64,
+        StackType::Load {
+            offset,
+            align,
+            memory,
+        }
+        | StackType::Store {
+            offset,
+            align,
+            memory,
+        } => offset ^ ((*align as u64) << 32) ^ ((*memory as u64) << 40),
+        // The arity is part of the operator's identity (it distinguishes
+        // e.g. a unary from a binary op); the defining operator index is
+        // deliberately excluded so equivalent computations fingerprint alike.
+        StackType::IndexAtCode(_, arity) => *arity as u64,
+        // A `Phi`'s identity can't be folded from a payload the way other
+        // operators' can (it has none), and its operands already come from
+        // every predecessor that reaches it, so there's nothing stable left
+        // to seed with beyond the shared `tag`.
+        StackType::Drop | StackType::Undef | StackType::Phi => 0,
+    };
+
+    ((tag as u128) << 64) | payload as u128
+}
+
+/// Folds an operand's fingerprint into the running fingerprint `h` using a
+/// commutative-unsafe (operand-order-sensitive) mix over both 64-bit halves.
+fn mix_fingerprint(h: u128, operand: u128) -> u128 {
+    let (mut hi, mut lo) = ((h >> 64) as u64, h as u64);
+    let (xi, xl) = ((operand >> 64) as u64, operand as u64);
+    hi = (hi ^ xi).wrapping_mul(FINGERPRINT_PRIME).rotate_left(13);
+    lo = (lo ^ xl).wrapping_mul(FINGERPRINT_PRIME).rotate_left(29);
+    ((hi as u128) << 64) | lo as u128
+}
+
+/// Records a successor/predecessor edge between two basic blocks, skipping
+/// duplicates so fall-through and explicit-branch edges to the same target
+/// don't get linked twice.
+fn add_edge(blocks: &mut [CfgBlock], from: usize, to: usize) {
+    if !blocks[from].succs.contains(&to) {
+        blocks[from].succs.push(to);
+    }
+    if !blocks[to].preds.contains(&from) {
+        blocks[to].preds.push(from);
+    }
+}
+
+/// Adds the straight-line successor edge for block `bidx`, i.e. the edge
+/// taken when its last operator isn't itself a branch.
+///
+/// The block immediately after an `if`'s then-arm is its `else` arm, but
+/// control only reaches `else` by branching there explicitly (or not at
+/// all) — falling off the end of a then-arm skips the else-arm entirely and
+/// joins at the matching `end`. So when the next block opens with `Else`,
+/// this redirects the fall-through edge to `targets`' innermost entry (the
+/// `if`'s matching `end`, pushed when the `if` itself was visited) instead
+/// of linking straight to the `else` block.
+fn fallthrough_edge(
+    operators: &[OperatorAndByteOffset],
+    blocks: &mut Vec<CfgBlock>,
+    bidx: usize,
+    targets: &[usize],
+) {
+    let next = bidx + 1;
+    if next >= blocks.len() {
+        return;
+    }
+    if matches!(operators[blocks[next].range.start].0, Operator::Else) {
+        if let Some(&end_target) = targets.last() {
+            let end_block = blocks
+                .iter()
+                .position(|b| end_target >= b.range.start && end_target < b.range.end)
+                .unwrap();
+            add_edge(blocks, bidx, end_block);
+            return;
+        }
+    }
+    add_edge(blocks, bidx, next);
+}
+
+/// Finds the operator index of the `end` that matches the `block`/`if`
+/// opening at `open_idx`, by tracking nesting depth forward from it.
+fn find_matching_end(operators: &[OperatorAndByteOffset], open_idx: usize) -> Option<usize> {
+    let mut depth = 0i32;
+    for idx in open_idx..operators.len() {
+        let (operator, _) = &operators[idx];
+        match operator {
+            Operator::Block { .. } | Operator::Loop { .. } | Operator::If { .. } => depth += 1,
+            Operator::End => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Like `find_matching_end`, but for an `if` specifically: also returns the
+/// index of its own `else` (at the same nesting depth, not a nested `if`'s),
+/// if it has one, since an `if` needs both to link its condition-false edge.
+fn find_if_targets(operators: &[OperatorAndByteOffset], if_idx: usize) -> Option<(Option<usize>, usize)> {
+    let mut depth = 0i32;
+    let mut else_idx = None;
+    for idx in if_idx..operators.len() {
+        let (operator, _) = &operators[idx];
+        match operator {
+            Operator::Block { .. } | Operator::Loop { .. } | Operator::If { .. } => depth += 1,
+            Operator::Else if depth == 1 => else_idx = Some(idx),
+            Operator::End => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((else_idx, idx));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Computes a reverse-postorder numbering of the CFG's blocks via a DFS from
+/// the entry block (block `0`), returning the block ids ordered by RPO
+/// position and, symmetrically, the RPO position of each block id.
+fn reverse_postorder(blocks: &[CfgBlock]) -> (Vec<usize>, Vec<usize>) {
+    let mut visited = vec![false; blocks.len()];
+    let mut postorder = Vec::with_capacity(blocks.len());
+
+    // Iterative post-order DFS to avoid recursion limits on deeply nested
+    // functions.
+    let mut stack = vec![(0usize, 0usize)];
+    visited[0] = true;
+    while let Some((node, child_idx)) = stack.pop() {
+        if child_idx < blocks[node].succs.len() {
+            let succ = blocks[node].succs[child_idx];
+            stack.push((node, child_idx + 1));
+            if !visited[succ] {
+                visited[succ] = true;
+                stack.push((succ, 0));
+            }
+        } else {
+            postorder.push(node);
+        }
+    }
+
+    postorder.reverse();
+
+    // Any block unreachable from the entry (dead code) is appended at the end
+    // so every block still gets an RPO number. This must happen *after* the
+    // reverse above: appending before it would put these blocks first in RPO
+    // order, and `compute_idoms` (`order[0]`) would then mistake one of them
+    // for the entry block instead of block `0`.
+    for (idx, seen) in visited.iter().enumerate() {
+        if !seen {
+            postorder.push(idx);
+        }
+    }
+
+    let mut number = vec![0usize; blocks.len()];
+    for (pos, &block) in postorder.iter().enumerate() {
+        number[block] = pos;
+    }
+    (postorder, number)
+}
+
+/// Computes immediate dominators with the iterative Cooper-Harvey-Kennedy
+/// algorithm, using the reverse-postorder numbering for the `intersect` walk.
+fn compute_idoms(blocks: &[CfgBlock], order: &[usize], number: &[usize]) -> Vec<usize> {
+    const UNDEFINED: usize = usize::MAX;
+    let entry = order[0];
+    let mut idom = vec![UNDEFINED; blocks.len()];
+    idom[entry] = entry;
+
+    fn intersect(idom: &[usize], number: &[usize], mut a: usize, mut b: usize) -> usize {
+        while a != b {
+            while number[a] > number[b] {
+                a = idom[a];
+            }
+            while number[b] > number[a] {
+                b = idom[b];
+            }
+        }
+        a
+    }
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &block in order.iter() {
+            if block == entry {
+                continue;
+            }
+            let mut new_idom = UNDEFINED;
+            for &pred in &blocks[block].preds {
+                if idom[pred] == UNDEFINED {
+                    continue;
+                }
+                new_idom = if new_idom == UNDEFINED {
+                    pred
+                } else {
+                    intersect(&idom, number, pred, new_idom)
+                };
+            }
+            if new_idom != UNDEFINED && idom[block] != new_idom {
+                idom[block] = new_idom;
+                changed = true;
+            }
+        }
+    }
+
+    // Unreachable blocks (no processed predecessor) stay dominated by
+    // themselves; they can't affect reaching-definitions for reachable uses.
+    for (block, dom) in idom.iter_mut().enumerate() {
+        if *dom == UNDEFINED {
+            *dom = block;
+        }
+    }
+    idom
+}
+
 impl<'a> DFGIcator {
     pub fn new() -> Self {
-        DFGIcator {}
+        Self::with_capacity(0)
+    }
+
+    /// Creates a `DFGIcator` whose scratch buffers are preallocated to hold
+    /// around `capacity` stack entries, amortizing allocation across a
+    /// mutation campaign that calls `get_dfg` many times on the same
+    /// `DFGIcator`.
+    pub fn with_capacity(capacity: usize) -> Self {
+        DFGIcator {
+            scratch: DfgScratch::with_capacity(capacity),
+        }
+    }
+
+    /// Returns `dfg`'s buffers to this `DFGIcator`'s scratch pool so the
+    /// next `get_dfg` call reuses their allocation instead of starting from
+    /// empty `Vec`s/`HashMap`s. Call this once `dfg` is no longer needed.
+    pub fn recycle(&mut self, dfg: MiniDFG) {
+        self.scratch.dfg_map = dfg.entries;
+        self.scratch.operatormap = dfg.map;
+        self.scratch.parents = dfg.parents;
+        self.scratch.fingerprints = dfg.fingerprints;
+        self.scratch.reset();
     }
 
     /// Linear algorithm  to detect the basic block
@@ -175,6 +671,175 @@ impl<'a> DFGIcator {
         }
     }
 
+    /// Splits the whole operator stream of a function into basic blocks and
+    /// links them into a control-flow graph with reverse-postorder numbering
+    /// and immediate dominators.
+    ///
+    /// A new block starts at operator `0`, right after every `Br`, `BrIf`,
+    /// `BrTable`, `If`, `Loop`, `Block`, `Return` and `Unreachable`, and at
+    /// every `Else`/`End` (since `End` is always a valid branch target in
+    /// Wasm). Branch targets are resolved through a stack that remembers, for
+    /// every currently open `block`/`loop`/`if`, which operator index a `br`
+    /// of the corresponding depth lands on: the matching `end` for
+    /// `block`/`if` (an `if` and its `else` share the same target), or the
+    /// `loop` header itself for `loop`.
+    pub fn build_cfg(&self, operators: &[OperatorAndByteOffset]) -> Option<ControlFlowGraph> {
+        if operators.is_empty() {
+            return None;
+        }
+
+        // First pass: find leaders, i.e. operator indexes that start a new block.
+        let mut leaders = vec![0usize];
+        for (idx, (operator, _)) in operators.iter().enumerate() {
+            match operator {
+                Operator::Br { .. }
+                | Operator::BrIf { .. }
+                | Operator::BrTable { .. }
+                | Operator::Return
+                | Operator::Unreachable
+                | Operator::If { .. }
+                | Operator::Loop { .. }
+                | Operator::Block { .. } => {
+                    if idx + 1 < operators.len() {
+                        leaders.push(idx + 1);
+                    }
+                }
+                Operator::Else | Operator::End => {
+                    leaders.push(idx);
+                    if idx + 1 < operators.len() {
+                        leaders.push(idx + 1);
+                    }
+                }
+                _ => {}
+            }
+        }
+        leaders.sort_unstable();
+        leaders.dedup();
+
+        let blocks: Vec<CfgBlock> = leaders
+            .iter()
+            .enumerate()
+            .map(|(i, &start)| {
+                let end = leaders.get(i + 1).copied().unwrap_or(operators.len());
+                CfgBlock {
+                    range: Range { start, end },
+                    succs: vec![],
+                    preds: vec![],
+                }
+            })
+            .collect();
+        let mut blocks = blocks;
+
+        let block_of = |op_idx: usize, blocks: &[CfgBlock]| -> usize {
+            blocks
+                .iter()
+                .position(|b| op_idx >= b.range.start && op_idx < b.range.end)
+                .unwrap()
+        };
+
+        // Second pass: a stack of branch targets, recorded as the operator
+        // index a `br` at that depth should land on. Seeded with the
+        // function body's own end so a `br`/fall-off-the-end at the
+        // outermost depth (outside every explicit `block`/`loop`/`if`)
+        // resolves to it, mirroring the implicit outer "block" Wasm
+        // validation wraps every function body in.
+        let mut targets: Vec<usize> = vec![operators.len() - 1];
+        let mut reducible = true;
+
+        for (bidx, block) in blocks.clone().iter().enumerate() {
+            let last = block.range.end - 1;
+            let (operator, _) = &operators[last];
+            match operator {
+                Operator::Block { .. } => {
+                    // A `br` out of this construct lands on the matching `end`,
+                    // which is itself a leader and therefore a block on its own.
+                    let matching_end = match find_matching_end(operators, last) {
+                        Some(end_idx) => end_idx,
+                        None => {
+                            reducible = false;
+                            last
+                        }
+                    };
+                    targets.push(matching_end);
+                    fallthrough_edge(operators, &mut blocks, bidx, &targets);
+                }
+                Operator::If { .. } => {
+                    // Unlike `block`, an `if` may have two arms: the `then`
+                    // arm right after it (taken when the condition is true,
+                    // reached here by ordinary fall-through) and an `else`
+                    // arm (taken when it's false). Both share the same
+                    // matching `end` as their `br` target.
+                    let (else_idx, matching_end) = match find_if_targets(operators, last) {
+                        Some(if_targets) => if_targets,
+                        None => {
+                            reducible = false;
+                            (None, last)
+                        }
+                    };
+                    targets.push(matching_end);
+                    fallthrough_edge(operators, &mut blocks, bidx, &targets);
+                    let false_target = else_idx.unwrap_or(matching_end);
+                    let false_block = block_of(false_target, &blocks);
+                    add_edge(&mut blocks, bidx, false_block);
+                }
+                Operator::Loop { .. } => {
+                    // A `br` out of a loop re-enters at its header.
+                    targets.push(last);
+                    fallthrough_edge(operators, &mut blocks, bidx, &targets);
+                }
+                Operator::Else => {
+                    // `if`/`else` share the same branch target; nothing to pop/push.
+                    fallthrough_edge(operators, &mut blocks, bidx, &targets);
+                }
+                Operator::End => {
+                    if targets.pop().is_none() {
+                        reducible = false;
+                    }
+                    fallthrough_edge(operators, &mut blocks, bidx, &targets);
+                }
+                Operator::Br { relative_depth } | Operator::BrIf { relative_depth } => {
+                    if let Some(&target_op) = targets.iter().rev().nth(*relative_depth as usize) {
+                        let target_block = block_of(target_op, &blocks);
+                        add_edge(&mut blocks, bidx, target_block);
+                    } else {
+                        reducible = false;
+                    }
+                    if matches!(operator, Operator::BrIf { .. }) {
+                        fallthrough_edge(operators, &mut blocks, bidx, &targets);
+                    }
+                }
+                Operator::BrTable { targets: table } => {
+                    let mut depths: Vec<u32> = table.targets().filter_map(|t| t.ok()).collect();
+                    depths.push(table.default());
+                    for depth in depths {
+                        if let Some(&target_op) = targets.iter().rev().nth(depth as usize) {
+                            let target_block = block_of(target_op, &blocks);
+                            add_edge(&mut blocks, bidx, target_block);
+                        } else {
+                            reducible = false;
+                        }
+                    }
+                }
+                Operator::Return | Operator::Unreachable => {
+                    // No fall-through successor.
+                }
+                _ => {
+                    fallthrough_edge(operators, &mut blocks, bidx, &targets);
+                }
+            }
+        }
+
+        let (order, number) = reverse_postorder(&blocks);
+        let idom = compute_idoms(&blocks, &order, &number);
+
+        Some(ControlFlowGraph {
+            blocks,
+            rpo: number,
+            idom,
+            reducible,
+        })
+    }
+
     fn push_node(
         operator: StackType,
         operator_idx: usize,
@@ -193,6 +858,7 @@ impl<'a> DFGIcator {
         } else {
             true
         };
+        let is_pure = operator.is_pure();
         let newnode = StackEntry {
             operator,
             operands,
@@ -200,6 +866,11 @@ impl<'a> DFGIcator {
             entry_idx,
             color,
             operator_idx,
+            is_pure,
+            // Filled in by `get_dfg` once the whole DFG is known.
+            use_count: 0,
+            inlinable: false,
+            fingerprint: 0,
         };
 
         operatormap.insert(operator_idx, entry_idx);
@@ -234,6 +905,10 @@ impl<'a> DFGIcator {
                     entry_idx,
                     color: 0, // 0 color is undefined
                     operator_idx,
+                    is_pure: false,
+                    use_count: 0,
+                    inlinable: false,
+                    fingerprint: 0,
                 }; // Means not reachable
                 if insertindfg {
                     operatormap.insert(operator_idx, entry_idx);
@@ -251,31 +926,29 @@ impl<'a> DFGIcator {
         idx
     }
 
-    /// This method should build lane dfg information
-    /// It returns a map of operator indexes over the function operators,
-    /// in which every key refers to a vector of ranges determining the operands
-    /// in the code
+    /// Processes one contiguous range of `operators` (a single basic block)
+    /// against the given scratch buffers, starting from `stack`'s current
+    /// contents instead of always starting empty. `get_dfg` calls this once
+    /// with an empty `stack`; `get_function_dfg` calls it once per
+    /// control-flow-graph block, seeding `stack` from the block's
+    /// predecessor(s) so values flow across basic block boundaries.
     ///
-    /// This process can is done inside basic blocsks, control flow information
-    /// is not taken into account in the peephole mutators
-    pub fn get_dfg(
+    /// Returns the updated buffers and the color the next range should start
+    /// from, or `None` if `range` contains an unsupported operator.
+    #[allow(clippy::too_many_arguments)]
+    fn process_operator_range(
         &mut self,
         info: &ModuleInfo,
         operators: &'a [OperatorAndByteOffset],
-        basicblock: &BBlock,
+        range: Range,
         locals: &Vec<PrimitiveTypeInfo>,
-    ) -> Option<MiniDFG> {
-        // lets handle the stack
-        let mut dfg_map = Vec::new();
-        let mut operatormap: HashMap<usize, usize> = HashMap::new(); // operator index to stack index
-        let mut stack: Vec<usize> = Vec::new();
-        let mut parents: Vec<i32> = Vec::new();
-        let mut color = 1; // start with color 1 since 0 is undef
-                           // Create a DFG from the BB
-                           // Start from the first operator and simulate the stack...
-                           // If an operator is missing in the stack then it probably comes from a previous BB
-
-        for idx in basicblock.range.start..basicblock.range.end {
+        mut dfg_map: Vec<StackEntry>,
+        mut operatormap: HashMap<usize, usize>,
+        mut stack: Vec<usize>,
+        mut parents: Vec<i32>,
+        mut color: u32,
+    ) -> Option<(Vec<StackEntry>, HashMap<usize, usize>, Vec<usize>, Vec<i32>, u32)> {
+        for idx in range.start..range.end {
             // We dont care about the jump
             let (operator, _) = &operators[idx];
             // Check if it is not EOF
@@ -466,7 +1139,7 @@ impl<'a> DFGIcator {
                     // Augnment the color since the next operations could be inconsistent
                     color += 1;
                 }
-                Operator::I32Store {..} | Operator::I64Store {..} => {
+                Operator::I32Store { memarg } | Operator::I64Store { memarg } => {
                     let offset = DFGIcator::pop_operand(
                         &mut stack,
                         &mut dfg_map,
@@ -476,7 +1149,11 @@ impl<'a> DFGIcator {
                         false,
                     );
                     let idx = DFGIcator::push_node(
-                        StackType::IndexAtCode(idx, 1),
+                        StackType::Store {
+                            offset: memarg.offset,
+                            align: memarg.align,
+                            memory: memarg.memory,
+                        },
                         idx,
                         &mut dfg_map,
                         &mut operatormap,
@@ -550,8 +1227,16 @@ impl<'a> DFGIcator {
 
                     parents[offset] = idx as i32;
                 }
-                Operator::I32Eqz => {
-                    let operand = DFGIcator::pop_operand(
+                // `operators.in` + `build.rs` expand into one arm per line
+                // there: a unary or binary operator that pops its operands,
+                // pushes an `IndexAtCode(idx, arity)` node over them, and
+                // fixes up `parents`, identical in shape to the hand-written
+                // arms elsewhere in this match. This is also where the
+                // f32/f64 arithmetic family and the previously-unsupported
+                // i64 bit-counting/sign-extension ops come from.
+                include!(concat!(env!("OUT_DIR"), "/operators_generated.rs"));
+                Operator::Drop => {
+                    let arg = DFGIcator::pop_operand(
                         &mut stack,
                         &mut dfg_map,
                         idx,
@@ -559,22 +1244,30 @@ impl<'a> DFGIcator {
                         &mut parents,
                         false,
                     );
+
                     let idx = DFGIcator::push_node(
-                        StackType::IndexAtCode(idx, 1),
+                        StackType::Drop,
                         idx,
                         &mut dfg_map,
                         &mut operatormap,
                         &mut stack,
-                        vec![operand],
+                        vec![arg], // reverse order
                         &mut parents,
                         color,
-                        PrimitiveTypeInfo::I32,
+                        PrimitiveTypeInfo::Empty,
                     );
 
-                    parents[operand] = idx as i32;
+                    parents[arg] = idx as i32;
+                    //color += 1;
                 }
-                Operator::I64Eqz => {
-                    let operand = DFGIcator::pop_operand(
+                // `get_function_dfg`'s basic blocks, unlike `get_dfg`'s,
+                // include the leading `if`'s condition check, since
+                // `build_cfg` ends a block *after* `if`/`loop`/`block` rather
+                // than before it. The arm and merge it opens are resolved by
+                // the phi-insertion logic in `get_function_dfg` itself, not
+                // here, so this just records the popped condition.
+                Operator::If { .. } => {
+                    let cond = DFGIcator::pop_operand(
                         &mut stack,
                         &mut dfg_map,
                         idx,
@@ -582,226 +1275,351 @@ impl<'a> DFGIcator {
                         &mut parents,
                         false,
                     );
-                    let idx = DFGIcator::push_node(
-                        StackType::IndexAtCode(idx, 1),
-                        idx,
-                        &mut dfg_map,
-                        &mut operatormap,
-                        &mut stack,
-                        vec![operand],
-                        &mut parents,
+                    let entry_idx = dfg_map.len();
+                    let newnode = StackEntry {
+                        operator: StackType::IndexAtCode(idx, 1),
+                        operands: vec![cond],
+                        return_type: PrimitiveTypeInfo::Empty,
+                        entry_idx,
                         color,
-                        PrimitiveTypeInfo::I32,
-                    );
-
-                    parents[operand] = idx as i32;
-                }
-                Operator::I64Add
-                | Operator::I64Sub
-                | Operator::I64Mul
-                | Operator::I64DivS
-                | Operator::I64DivU
-                | Operator::I64Shl
-                | Operator::I64ShrS
-                | Operator::I64Xor
-                | Operator::I64Or
-                | Operator::I64And
-                | Operator::I64Rotl
-                | Operator::I64Rotr
-                | Operator::I64ShrU
-                | Operator::I64RemS
-                | Operator::I64RemU
-                => {
-                    let leftidx = DFGIcator::pop_operand(
-                        &mut stack,
-                        &mut dfg_map,
-                        idx,
-                        &mut operatormap,
-                        &mut parents,
-                        false,
-                    );
-                    let rightidx = DFGIcator::pop_operand(
-                        &mut stack,
-                        &mut dfg_map,
-                        idx,
-                        &mut operatormap,
-                        &mut parents,
-                        false,
-                    );
-                    // The operands should not be the same
-                    assert_ne!(leftidx, rightidx);
-
-                    let idx = DFGIcator::push_node(
-                        StackType::IndexAtCode(idx, 2),
-                        idx,
-                        &mut dfg_map,
-                        &mut operatormap,
-                        &mut stack,
-                        vec![rightidx, leftidx], // reverse order
-                        &mut parents,
+                        operator_idx: idx,
+                        is_pure: false,
+                        use_count: 0,
+                        inlinable: false,
+                        fingerprint: 0,
+                    };
+                    dfg_map.push(newnode);
+                    parents.push(-1);
+                    parents[cond] = entry_idx as i32;
+                }
+                // `Operator::I32WrapI64` is now generated by `operators.in`
+                // above, alongside the rest of the integer conversions.
+                Operator::Block { .. }
+                | Operator::Loop { .. }
+                | Operator::Else
+                | Operator::End
+                | Operator::Nop
+                | Operator::Br { .. }
+                | Operator::BrTable { .. }
+                | Operator::BrIf { .. }
+                | Operator::Return
+                | Operator::Unreachable => {
+                    // Write this down to do a small change in the original wasm
+                    let entry_idx = dfg_map.len();
+                    let newnode = StackEntry {
+                        operator: StackType::IndexAtCode(idx, 0),
+                        operands: vec![],
+                        return_type: PrimitiveTypeInfo::Empty,
+                        entry_idx,
                         color,
-                        PrimitiveTypeInfo::I64,
-                    );
+                        operator_idx: idx,
+                        is_pure: false,
+                        use_count: 0,
+                        inlinable: false,
+                        fingerprint: 0,
+                    };
+                    dfg_map.push(newnode);
+                    parents.push(-1);
+                }
+                _ => {
+                    // If the operator is not implemented, break the mutation of this Basic Block
+                    return None;
+                }
+            }
+        }
 
-                    parents[leftidx] = idx as i32;
-                    parents[rightidx] = idx as i32;
-                }
-                Operator::I32Add
-                | Operator::I32Sub
-                | Operator::I32Eq
-                | Operator::I32Ne
-                | Operator::I32LtS
-                | Operator::I32LtU
-                | Operator::I32GtS
-                | Operator::I32GtU
-                | Operator::I32LeS
-                | Operator::I32LeU
-                | Operator::I32GeS
-                | Operator::I32GeU
-                | Operator::I32Mul
-                | Operator::I32DivS
-                | Operator::I32DivU
-                | Operator::I32Shl
-                | Operator::I32ShrS
-                | Operator::I32Xor
-                | Operator::I32Or
-                | Operator::I64Eq
-                | Operator::I64Ne
-                | Operator::I64LtS
-                | Operator::I64LtU
-                | Operator::I64GtS
-                | Operator::I64GtU
-                | Operator::I64LeS
-                | Operator::I64LeU
-                | Operator::I64GeS
-                | Operator::I64GeU
-                | Operator::I32And
-                | Operator::I32ShrU
-                | Operator::I32Rotl
-                | Operator::I32Rotr
-                | Operator::I32RemS
-                | Operator::I32RemU
-                => {
-                    let leftidx = DFGIcator::pop_operand(
-                        &mut stack,
-                        &mut dfg_map,
-                        idx,
-                        &mut operatormap,
-                        &mut parents,
-                        false,
-                    );
-                    let rightidx = DFGIcator::pop_operand(
-                        &mut stack,
-                        &mut dfg_map,
-                        idx,
-                        &mut operatormap,
-                        &mut parents,
-                        false,
-                    );
+        Some((dfg_map, operatormap, stack, parents, color))
+    }
 
-                    // The operands should not be the same
-                    assert_ne!(leftidx, rightidx);
+    /// This method should build lane dfg information
+    /// It returns a map of operator indexes over the function operators,
+    /// in which every key refers to a vector of ranges determining the operands
+    /// in the code
+    ///
+    /// This process can is done inside basic blocsks, control flow information
+    /// is not taken into account in the peephole mutators
+    ///
+    /// This is a deliberate, narrower contract than [`get_function_dfg`]:
+    /// `basicblock` is processed in isolation, so an operand the block
+    /// doesn't itself define is genuinely unknown here (there is no
+    /// predecessor in scope to have defined it) and falls back to
+    /// `StackType::Undef` via `pop_operand`, rather than being resolved
+    /// through `self.build_cfg`'s dominance info. Callers that want values
+    /// threaded across block boundaries instead of `Undef` need
+    /// [`get_function_dfg`], which walks the whole function's
+    /// control-flow graph and seeds each block's stack from its
+    /// predecessor(s).
+    ///
+    /// [`get_function_dfg`]: Self::get_function_dfg
+    pub fn get_dfg(
+        &mut self,
+        info: &ModuleInfo,
+        operators: &'a [OperatorAndByteOffset],
+        basicblock: &BBlock,
+        locals: &Vec<PrimitiveTypeInfo>,
+    ) -> Option<MiniDFG> {
+        // Draw this call's buffers out of the scratch pool instead of
+        // allocating fresh ones; `self.scratch` gets them back (cleared) on
+        // the next `recycle` call.
+        self.scratch.reset();
+        let dfg_map = core::mem::take(&mut self.scratch.dfg_map);
+        let operatormap = core::mem::take(&mut self.scratch.operatormap); // operator index to stack index
+        let stack = core::mem::take(&mut self.scratch.stack);
+        let parents = core::mem::take(&mut self.scratch.parents);
+        let color = 1; // start with color 1 since 0 is undef
+                           // Create a DFG from the BB
+                           // Start from the first operator and simulate the stack...
+                           // If an operator is missing in the stack then it probably comes from a previous BB
 
-                    let idx = DFGIcator::push_node(
-                        StackType::IndexAtCode(idx, 2),
-                        idx,
-                        &mut dfg_map,
-                        &mut operatormap,
-                        &mut stack,
-                        vec![rightidx, leftidx], // reverse order
-                        &mut parents,
-                        color,
-                        PrimitiveTypeInfo::I32,
-                    );
+        let (new_dfg_map, new_operatormap, new_stack, new_parents, _color) = self
+            .process_operator_range(
+                info,
+                operators,
+                basicblock.range,
+                locals,
+                dfg_map,
+                operatormap,
+                stack,
+                parents,
+                color,
+            )?;
+        let mut dfg_map = new_dfg_map;
+        let operatormap = new_operatormap;
+        let stack = new_stack;
+        let parents = new_parents;
+
+        // `stack` isn't part of the returned `MiniDFG`; give it back to the
+        // pool immediately rather than waiting for `recycle`.
+        self.scratch.stack = stack;
+
+        // Now that every entry has been created, compute how many times each
+        // one is referenced as an operand, and derive `inlinable` from that
+        // plus purity. This can only be done after the fact since an entry's
+        // use count depends on entries that are pushed later.
+        let mut use_counts = vec![0u32; dfg_map.len()];
+        for entry in &dfg_map {
+            for &operand in &entry.operands {
+                use_counts[operand] += 1;
+            }
+        }
+        for (entry, use_count) in dfg_map.iter_mut().zip(use_counts) {
+            entry.use_count = use_count;
+            entry.inlinable = entry.is_pure && entry.use_count <= 1;
+        }
 
-                    parents[leftidx] = idx as i32;
-                    parents[rightidx] = idx as i32;
-                }
-                Operator::Drop => {
-                    let arg = DFGIcator::pop_operand(
-                        &mut stack,
-                        &mut dfg_map,
-                        idx,
-                        &mut operatormap,
-                        &mut parents,
-                        false,
-                    );
+        // Fingerprint every entry bottom-up, reusing the pooled `fingerprints`
+        // map from a prior call. Operands always sit at a smaller index than
+        // the entry referencing them (they were pushed first), so a single
+        // forward pass suffices.
+        let mut fingerprints = core::mem::take(&mut self.scratch.fingerprints);
+        for entry_idx in 0..dfg_map.len() {
+            let operands = dfg_map[entry_idx].operands.clone();
+            let mut fp = seed_fingerprint(&dfg_map[entry_idx], operators);
+            for operand in operands {
+                fp = mix_fingerprint(fp, dfg_map[operand].fingerprint);
+            }
+            dfg_map[entry_idx].fingerprint = fp;
+            fingerprints.entry(fp).or_insert_with(Vec::new).push(entry_idx);
+        }
 
-                    let idx = DFGIcator::push_node(
-                        StackType::Drop,
-                        idx,
-                        &mut dfg_map,
-                        &mut operatormap,
-                        &mut stack,
-                        vec![arg], // reverse order
-                        &mut parents,
-                        color,
-                        PrimitiveTypeInfo::Empty,
-                    );
+        Some(MiniDFG {
+            entries: dfg_map,
+            map: operatormap,
+            parents,
+            fingerprints,
+            block_roots: vec![],
+        })
+    }
 
-                    parents[arg] = idx as i32;
-                    //color += 1;
-                }
-                // conversion between integers
-                Operator::I32WrapI64 => {
-                    let arg = DFGIcator::pop_operand(
-                        &mut stack,
-                        &mut dfg_map,
-                        idx,
-                        &mut operatormap,
-                        &mut parents,
-                        false,
-                    );
+    /// Builds a DFG across an entire function's control-flow graph rather
+    /// than a single basic block, threading values that cross block
+    /// boundaries as actual data-flow edges instead of `get_dfg`'s
+    /// "missing operand" (`StackType::Undef`) treatment.
+    ///
+    /// Blocks are visited in reverse-postorder, so every predecessor reached
+    /// by a forward edge has already contributed its exit stack by the time
+    /// a block is processed. A block inherits its single predecessor's exit
+    /// stack directly; where multiple predecessors disagree on a stack slot,
+    /// a [`StackType::Phi`] entry is created with one operand per
+    /// predecessor. A loop header's back-edge predecessor hasn't been
+    /// visited yet when the header itself is processed, so its contribution
+    /// to the header's phis is recorded as a [`PendingPhi`] and appended
+    /// once the whole function has been walked.
+    ///
+    /// Returns `None` if `operators` has no reducible control-flow graph (see
+    /// `ControlFlowGraph::reducible`) or contains an operator
+    /// `process_operator_range` doesn't support.
+    pub fn get_function_dfg(
+        &mut self,
+        info: &ModuleInfo,
+        operators: &'a [OperatorAndByteOffset],
+        locals: &Vec<PrimitiveTypeInfo>,
+    ) -> Option<MiniDFG> {
+        let cfg = self.build_cfg(operators)?;
+        if !cfg.reducible {
+            return None;
+        }
 
-                    let idx = DFGIcator::push_node(
-                        StackType::IndexAtCode(idx, 1),
-                        idx,
+        self.scratch.reset();
+        let mut dfg_map = core::mem::take(&mut self.scratch.dfg_map);
+        let mut operatormap = core::mem::take(&mut self.scratch.operatormap);
+        let mut parents = core::mem::take(&mut self.scratch.parents);
+        let mut color = 1u32; // start with color 1 since 0 is undef
+
+        let mut order: Vec<usize> = (0..cfg.blocks.len()).collect();
+        order.sort_unstable_by_key(|&block| cfg.rpo[block]);
+
+        let mut exit_stack: Vec<Option<Vec<usize>>> = vec![None; cfg.blocks.len()];
+        let mut block_roots: Vec<Vec<usize>> = vec![vec![]; cfg.blocks.len()];
+        let mut pending: Vec<PendingPhi> = vec![];
+
+        for &block in &order {
+            let preds = cfg.blocks[block].preds.clone();
+            let stack: Vec<usize> = if preds.is_empty() {
+                // Function entry: nothing flows in.
+                vec![]
+            } else if preds.len() == 1 && exit_stack[preds[0]].is_some() {
+                exit_stack[preds[0]].clone().unwrap()
+            } else {
+                let visited_preds: Vec<usize> = preds
+                    .iter()
+                    .copied()
+                    .filter(|&p| exit_stack[p].is_some())
+                    .collect();
+                if visited_preds.is_empty() {
+                    // Not a single predecessor has run yet; can't happen for a
+                    // reachable block in a reducible CFG walked in RPO order.
+                    return None;
+                }
+                let width = exit_stack[visited_preds[0]].as_ref().unwrap().len();
+                let mut merged = Vec::with_capacity(width);
+                for slot in 0..width {
+                    let defs: Vec<usize> = visited_preds
+                        .iter()
+                        .map(|&p| exit_stack[p].as_ref().unwrap()[slot])
+                        .collect();
+                    let all_resolved = visited_preds.len() == preds.len();
+                    if all_resolved && defs.iter().all(|&d| d == defs[0]) {
+                        merged.push(defs[0]);
+                        continue;
+                    }
+                    let return_type = dfg_map[defs[0]].return_type.clone();
+                    let phi_idx = DFGIcator::push_node(
+                        StackType::Phi,
+                        cfg.blocks[block].range.start,
                         &mut dfg_map,
                         &mut operatormap,
-                        &mut stack,
-                        vec![arg], // reverse order
+                        &mut merged,
+                        defs.clone(),
                         &mut parents,
                         color,
-                        PrimitiveTypeInfo::I32,
+                        return_type,
                     );
-
-                    parents[arg] = idx as i32;
-                }
-                Operator::Else
-                | Operator::End
-                | Operator::Nop
-                | Operator::Br { .. }
-                | Operator::BrTable { .. }
-                | Operator::BrIf { .. }
-                | Operator::Return
-                | Operator::Unreachable => {
-                    // Write this down to do a small change in the original wasm
-                    let entry_idx = dfg_map.len();
-                    let newnode = StackEntry {
-                        operator: StackType::IndexAtCode(idx, 0),
-                        operands: vec![],
-                        return_type: PrimitiveTypeInfo::Empty,
-                        entry_idx,
-                        color,
-                        operator_idx: idx,
-                    };
-                    dfg_map.push(newnode);
-                    parents.push(-1);
-                }
-                _ => {
-                    // If the operator is not implemented, break the mutation of this Basic Block
-                    return None;
+                    for &def in &defs {
+                        parents[def] = phi_idx as i32;
+                    }
+                    if !all_resolved {
+                        for &p in &preds {
+                            if exit_stack[p].is_none() {
+                                pending.push(PendingPhi {
+                                    phi_idx,
+                                    missing_pred: p,
+                                    stack_pos: slot,
+                                });
+                            }
+                        }
+                    }
                 }
+                merged
+            };
+
+            block_roots[block] = stack.clone();
+
+            let (new_dfg_map, new_operatormap, new_stack, new_parents, new_color) = self
+                .process_operator_range(
+                    info,
+                    operators,
+                    cfg.blocks[block].range,
+                    locals,
+                    dfg_map,
+                    operatormap,
+                    stack,
+                    parents,
+                    color,
+                )?;
+            dfg_map = new_dfg_map;
+            operatormap = new_operatormap;
+            parents = new_parents;
+            color = new_color + 1;
+            exit_stack[block] = Some(new_stack);
+        }
+
+        // Every block has now contributed an exit stack, including loop
+        // bodies; back-patch the back-edge operand each pending phi was
+        // still missing.
+        for p in &pending {
+            let def = exit_stack[p.missing_pred].as_ref()?.get(p.stack_pos).copied()?;
+            dfg_map[p.phi_idx].operands.push(def);
+            parents[def] = p.phi_idx as i32;
+        }
+
+        self.scratch.stack = vec![];
+
+        // Same use_count/inlinable/fingerprint post-pass as `get_dfg`. A
+        // loop-carried phi's back-edge operand can sit at a *larger* index
+        // than the phi itself (it's only known once the loop body has been
+        // walked), so its fingerprint may still be the default `0` here;
+        // since `Phi` is never `inlinable` this can't corrupt a GVN rewrite,
+        // only miss one, same as the existing `Undef` leaves already do.
+        let mut use_counts = vec![0u32; dfg_map.len()];
+        for entry in &dfg_map {
+            for &operand in &entry.operands {
+                use_counts[operand] += 1;
+            }
+        }
+        for (entry, use_count) in dfg_map.iter_mut().zip(use_counts) {
+            entry.use_count = use_count;
+            entry.inlinable = entry.is_pure && entry.use_count <= 1;
+        }
+
+        let mut fingerprints = core::mem::take(&mut self.scratch.fingerprints);
+        for entry_idx in 0..dfg_map.len() {
+            let operands = dfg_map[entry_idx].operands.clone();
+            let mut fp = seed_fingerprint(&dfg_map[entry_idx], operators);
+            for operand in operands {
+                fp = mix_fingerprint(fp, dfg_map[operand].fingerprint);
             }
+            dfg_map[entry_idx].fingerprint = fp;
+            fingerprints
+                .entry(fp)
+                .or_insert_with(Vec::new)
+                .push(entry_idx);
         }
+
         Some(MiniDFG {
             entries: dfg_map,
             map: operatormap,
             parents,
+            fingerprints,
+            block_roots,
         })
     }
 }
 
+/// A loop header's phi operand still waiting on its back-edge predecessor,
+/// recorded while `get_function_dfg` walks blocks in reverse-postorder (the
+/// back-edge source hasn't been visited yet when the header is) and resolved
+/// once every block has contributed an exit stack.
+struct PendingPhi {
+    /// Index into the in-progress `dfg_map` of the `StackType::Phi` entry.
+    phi_idx: usize,
+    /// Block id of the not-yet-visited predecessor this operand comes from.
+    missing_pred: usize,
+    /// Position in that predecessor's exit stack the operand is read from.
+    stack_pos: usize,
+}
+
+#[cfg(feature = "std")]
 impl MiniDFG {
     /// Pretty prints the DFG forest in a tree structure
     pub fn pretty_print(&self, operators: &Vec<OperatorAndByteOffset>) -> String {
@@ -885,8 +1703,62 @@ impl MiniDFG {
     }
 }
 
-impl std::fmt::Display for MiniDFG {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl MiniDFG {
+    /// Renders the DFG forest as a Graphviz `digraph`: one node per
+    /// `entries` entry, labeled with its operator and `operator_idx`, an
+    /// edge from each entry to every operand it reads, and `color` mapped to
+    /// `fillcolor` so the forest's independent colored subtrees (see
+    /// [`StackEntry::color`]) are visually distinguishable in any DOT
+    /// viewer, not just a color-capable ANSI terminal.
+    pub fn to_dot(&self, operators: &Vec<OperatorAndByteOffset>) -> String {
+        // Matches `pretty_print`'s ANSI palette, translated to DOT/SVG color
+        // names so a node's fill lines up with the color it would get in the
+        // terminal printer.
+        fn get_dot_color(color: u32) -> &'static str {
+            match color {
+                0 => "red",
+                1 => "green",
+                2 => "gold",
+                3 => "blue",
+                4 => "magenta",
+                5 => "cyan",
+                6 => "white",
+                7 => "green",
+                8 | 9 | 11 => "red",
+                10 => "green",
+                _ => "lightgray",
+            }
+        }
+
+        let mut builder = String::from("digraph DFG {\n");
+        builder.push_str("    node [shape=box, style=filled];\n");
+
+        for (entry_idx, entry) in self.entries.iter().enumerate() {
+            let (operator, _) = &operators[entry.operator_idx];
+            let label = format!("{:?}\\n(at {})", operator, entry.operator_idx)
+                .replace('"', "\\\"");
+            builder.push_str(&format!(
+                "    n{} [label=\"{}\", fillcolor=\"{}\"];\n",
+                entry_idx,
+                label,
+                get_dot_color(entry.color)
+            ));
+        }
+
+        for (entry_idx, entry) in self.entries.iter().enumerate() {
+            for operand in &entry.operands {
+                builder.push_str(&format!("    n{} -> n{};\n", entry_idx, operand));
+            }
+        }
+
+        builder.push_str("}\n");
+        builder
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Display for MiniDFG {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.write_str("DFG forest \n")?;
 
         // To get ansi colors
@@ -910,10 +1782,10 @@ impl std::fmt::Display for MiniDFG {
         fn write_child(
             minidfg: &MiniDFG,
             entryidx: usize,
-            f: &mut std::fmt::Formatter<'_>,
+            f: &mut core::fmt::Formatter<'_>,
             preffix: &str,
             childrenpreffix: &str,
-        ) -> std::fmt::Result {
+        ) -> core::fmt::Result {
             let entry = &minidfg.entries[entryidx];
             f.write_str(&preffix)?;
             let color = get_color(entry.color);
@@ -1029,19 +1901,30 @@ mod tests {
     }
 
     #[test]
-    fn test_dfg_build1() {
-        // A decent complex Wasm function
+    fn test_build_cfg_if_loop() {
+        // Same function as `test_dfg_getsinglebb`, now checked as a whole-function CFG.
         let original = &wat::parse_str(
             r#"
         (module
             (memory 1)
             (func (export "exported_func") (param i32) (result i32)
-                i32.const 32
-                drop
                 local.get 0
                 local.get 0
                 i32.add
+                i32.load
+                if
+                    i32.const 54
+                else
+                    i32.const 87
+                end
+                i32.const 56
                 i32.add
+                loop
+                    i32.const 1
+                    local.get 0
+                    i32.add
+                    local.set 0
+                end
             )
         )
         "#,
@@ -1069,12 +1952,12 @@ mod tests {
                         .collect::<wasmparser::Result<Vec<OperatorAndByteOffset>>>()
                         .unwrap();
 
-                    let bb = DFGIcator::new()
-                        .get_bb_from_operator(0, &operators)
-                        .unwrap();
-                    let roots =
-                        DFGIcator::new().get_dfg(&ModuleInfo::default(), &operators, &bb, &vec![]);
-                    assert!(roots.is_some())
+                    let cfg = DFGIcator::new().build_cfg(&operators).unwrap();
+                    assert!(cfg.reducible);
+                    // The entry block dominates every other block.
+                    for id in 0..cfg.blocks.len() {
+                        assert!(cfg.dominates(0, id));
+                    }
                 }
                 wasmparser::Payload::End => {
                     break;
@@ -1087,42 +1970,29 @@ mod tests {
     }
 
     #[test]
-    fn test_dfg_build2() {
-        // A decent complex Wasm function
+    fn test_build_cfg_entry_self_dominates_with_dead_block_and_loop_back_edge() {
+        // A `loop` as the function's very first instruction makes the entry
+        // block (block 0) the target of the loop's back edge, and the dead
+        // code right after `unreachable` creates a block `reverse_postorder`
+        // never reaches by DFS. Combining both used to corrupt RPO order
+        // (the unreachable block landed *before* the entry instead of after
+        // it) and make `compute_idoms` pick that dead block as the entry,
+        // leaving the true entry's `idom` pointing at some other block
+        // instead of itself.
         let original = &wat::parse_str(
             r#"
         (module
-            (memory 1)
             (func (export "exported_func") (param i32) (result i32)
-                i32.const 32
-                i32.load
-                i32.const 100
-                i32.load
-                i32.const 1
-                i32.gt_s
-                i32.const 1
-                i32.gt_u
-                i32.const 1
-                i32.lt_u
-                i32.const 1
-                i32.lt_s
-                i32.const 1
-                i32.ne
-                i32.const 1
-                i32.eq
-                i32.const 1
-                i32.eqz
-                i32.const 1
-                i32.le_s
-                i32.const 1
-                i32.le_u
-                i32.const 1
-                i32.ge_s
-                i32.const 1
-                i32.ge_u
-                local.set 0
-                i32.const 1
-                i32.add
+                loop
+                    local.get 0
+                    br_if 0
+                    unreachable
+                    i32.const 1
+                    local.get 0
+                    i32.add
+                    drop
+                end
+                i32.const 0
             )
         )
         "#,
@@ -1150,12 +2020,19 @@ mod tests {
                         .collect::<wasmparser::Result<Vec<OperatorAndByteOffset>>>()
                         .unwrap();
 
-                    let bb = DFGIcator::new()
-                        .get_bb_from_operator(7, &operators)
-                        .unwrap();
-                    let roots =
-                        DFGIcator::new().get_dfg(&ModuleInfo::default(), &operators, &bb, &vec![]);
-                    assert!(roots.is_some());
+                    let cfg = DFGIcator::new().build_cfg(&operators).unwrap();
+                    assert!(cfg.reducible);
+                    assert_eq!(cfg.idom[0], 0, "the entry block must dominate itself");
+                    // Blocks 0-2 (the `loop`, its body up to `br_if`, and the
+                    // `unreachable`) are the only ones reachable from the
+                    // entry; every later block is dead and dominated only by
+                    // itself.
+                    for id in 0..3 {
+                        assert!(cfg.dominates(0, id));
+                    }
+                    for id in 3..cfg.blocks.len() {
+                        assert!(!cfg.dominates(0, id));
+                    }
                 }
                 wasmparser::Payload::End => {
                     break;
@@ -1168,43 +2045,182 @@ mod tests {
     }
 
     #[test]
-    fn test_dfg_build3() {
+    fn test_dfg_build1() {
         // A decent complex Wasm function
         let original = &wat::parse_str(
             r#"
         (module
             (memory 1)
-            (global $0 i32 i32.const 0)
-            (func (export "exported_func") (result i32) (local i32)
-                i32.const 123
-                return
-                i32.const 312
-                i32.const 100
+            (func (export "exported_func") (param i32) (result i32)
+                i32.const 32
                 drop
-                local.set 0
                 local.get 0
-                local.set 0
-                i32.const 1230
-                local.tee 0
-                call 0
-                call 0
+                local.get 0
                 i32.add
-                drop
-                i32.const 900
-                global.get 0
-                drop
-                global.set 0
-                global.get 0
-                global.set 0
-                nop
-                nop
-                
-                i32.const 10
-                i32.const 20
-                i32.rotr
-
-                i32.const 10
-                i32.const 20
+                i32.add
+            )
+        )
+        "#,
+        )
+        .unwrap();
+
+        let mut parser = Parser::new(0);
+        let mut consumed = 0;
+        loop {
+            let (payload, size) = match parser.parse(&original[consumed..], true).unwrap() {
+                wasmparser::Chunk::NeedMoreData(_) => {
+                    panic!("This should not happen")
+                }
+                wasmparser::Chunk::Parsed { consumed, payload } => (payload, consumed),
+            };
+
+            consumed += size;
+
+            match payload {
+                wasmparser::Payload::CodeSectionEntry(reader) => {
+                    let operators = reader
+                        .get_operators_reader()
+                        .unwrap()
+                        .into_iter_with_offsets()
+                        .collect::<wasmparser::Result<Vec<OperatorAndByteOffset>>>()
+                        .unwrap();
+
+                    let bb = DFGIcator::new()
+                        .get_bb_from_operator(0, &operators)
+                        .unwrap();
+                    let roots =
+                        DFGIcator::new().get_dfg(&ModuleInfo::default(), &operators, &bb, &vec![]);
+                    assert!(roots.is_some())
+                }
+                wasmparser::Payload::End => {
+                    break;
+                }
+                _ => {
+                    // Do nothing
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dfg_build2() {
+        // A decent complex Wasm function
+        let original = &wat::parse_str(
+            r#"
+        (module
+            (memory 1)
+            (func (export "exported_func") (param i32) (result i32)
+                i32.const 32
+                i32.load
+                i32.const 100
+                i32.load
+                i32.const 1
+                i32.gt_s
+                i32.const 1
+                i32.gt_u
+                i32.const 1
+                i32.lt_u
+                i32.const 1
+                i32.lt_s
+                i32.const 1
+                i32.ne
+                i32.const 1
+                i32.eq
+                i32.const 1
+                i32.eqz
+                i32.const 1
+                i32.le_s
+                i32.const 1
+                i32.le_u
+                i32.const 1
+                i32.ge_s
+                i32.const 1
+                i32.ge_u
+                local.set 0
+                i32.const 1
+                i32.add
+            )
+        )
+        "#,
+        )
+        .unwrap();
+
+        let mut parser = Parser::new(0);
+        let mut consumed = 0;
+        loop {
+            let (payload, size) = match parser.parse(&original[consumed..], true).unwrap() {
+                wasmparser::Chunk::NeedMoreData(_) => {
+                    panic!("This should not happen")
+                }
+                wasmparser::Chunk::Parsed { consumed, payload } => (payload, consumed),
+            };
+
+            consumed += size;
+
+            match payload {
+                wasmparser::Payload::CodeSectionEntry(reader) => {
+                    let operators = reader
+                        .get_operators_reader()
+                        .unwrap()
+                        .into_iter_with_offsets()
+                        .collect::<wasmparser::Result<Vec<OperatorAndByteOffset>>>()
+                        .unwrap();
+
+                    let bb = DFGIcator::new()
+                        .get_bb_from_operator(7, &operators)
+                        .unwrap();
+                    let roots =
+                        DFGIcator::new().get_dfg(&ModuleInfo::default(), &operators, &bb, &vec![]);
+                    assert!(roots.is_some());
+                }
+                wasmparser::Payload::End => {
+                    break;
+                }
+                _ => {
+                    // Do nothing
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dfg_build3() {
+        // A decent complex Wasm function
+        let original = &wat::parse_str(
+            r#"
+        (module
+            (memory 1)
+            (global $0 i32 i32.const 0)
+            (func (export "exported_func") (result i32) (local i32)
+                i32.const 123
+                return
+                i32.const 312
+                i32.const 100
+                drop
+                local.set 0
+                local.get 0
+                local.set 0
+                i32.const 1230
+                local.tee 0
+                call 0
+                call 0
+                i32.add
+                drop
+                i32.const 900
+                global.get 0
+                drop
+                global.set 0
+                global.get 0
+                global.set 0
+                nop
+                nop
+                
+                i32.const 10
+                i32.const 20
+                i32.rotr
+
+                i32.const 10
+                i32.const 20
                 i32.rotl
                 i32.const 100
                 i32.const 50
@@ -1317,4 +2333,461 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_dfg_inlinable() {
+        // Each `local.get 0` is its own DFG entry consumed exactly once, so
+        // both are pure and inlinable, same as the `i32.const 32` operand.
+        let original = &wat::parse_str(
+            r#"
+        (module
+            (memory 1)
+            (func (export "exported_func") (param i32) (result i32)
+                local.get 0
+                local.get 0
+                i32.const 32
+                i32.add
+                i32.add
+            )
+        )
+        "#,
+        )
+        .unwrap();
+
+        let mut parser = Parser::new(0);
+        let mut consumed = 0;
+        loop {
+            let (payload, size) = match parser.parse(&original[consumed..], true).unwrap() {
+                wasmparser::Chunk::NeedMoreData(_) => {
+                    panic!("This should not happen")
+                }
+                wasmparser::Chunk::Parsed { consumed, payload } => (payload, consumed),
+            };
+
+            consumed += size;
+
+            match payload {
+                wasmparser::Payload::CodeSectionEntry(reader) => {
+                    let operators = reader
+                        .get_operators_reader()
+                        .unwrap()
+                        .into_iter_with_offsets()
+                        .collect::<wasmparser::Result<Vec<OperatorAndByteOffset>>>()
+                        .unwrap();
+
+                    let bb = DFGIcator::new()
+                        .get_bb_from_operator(4, &operators)
+                        .unwrap();
+                    let dfg = DFGIcator::new()
+                        .get_dfg(
+                            &ModuleInfo::default(),
+                            &operators,
+                            &bb,
+                            &vec![PrimitiveTypeInfo::I32],
+                        )
+                        .unwrap();
+
+                    let local_get_uses = dfg
+                        .entries
+                        .iter()
+                        .filter(|e| matches!(e.operator, super::StackType::LocalGet(0)))
+                        .count();
+                    assert_eq!(local_get_uses, 2);
+                    for entry in &dfg.entries {
+                        if matches!(entry.operator, super::StackType::LocalGet(0)) {
+                            assert_eq!(entry.use_count, 1);
+                            assert!(entry.is_pure);
+                            assert!(entry.inlinable);
+                        }
+                        if matches!(entry.operator, super::StackType::I32(32)) {
+                            assert!(entry.inlinable);
+                        }
+                    }
+                }
+                wasmparser::Payload::End => {
+                    break;
+                }
+                _ => {
+                    // Do nothing
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dfg_fingerprint_gvn() {
+        // The two `i32.add` subtrees are structurally identical (same
+        // constant operands, same operator), so they should be assigned
+        // equal fingerprints and grouped together in `fingerprints`. The
+        // third addition uses a different constant and must not collide.
+        let original = &wat::parse_str(
+            r#"
+        (module
+            (memory 1)
+            (func (export "exported_func") (param i32) (result i32)
+                i32.const 1
+                i32.const 2
+                i32.add
+                i32.const 1
+                i32.const 2
+                i32.add
+                i32.add
+                i32.const 1
+                i32.const 3
+                i32.add
+                i32.add
+            )
+        )
+        "#,
+        )
+        .unwrap();
+
+        let mut parser = Parser::new(0);
+        let mut consumed = 0;
+        loop {
+            let (payload, size) = match parser.parse(&original[consumed..], true).unwrap() {
+                wasmparser::Chunk::NeedMoreData(_) => {
+                    panic!("This should not happen")
+                }
+                wasmparser::Chunk::Parsed { consumed, payload } => (payload, consumed),
+            };
+
+            consumed += size;
+
+            match payload {
+                wasmparser::Payload::CodeSectionEntry(reader) => {
+                    let operators = reader
+                        .get_operators_reader()
+                        .unwrap()
+                        .into_iter_with_offsets()
+                        .collect::<wasmparser::Result<Vec<OperatorAndByteOffset>>>()
+                        .unwrap();
+
+                    let bb = DFGIcator::new()
+                        .get_bb_from_operator(10, &operators)
+                        .unwrap();
+                    let dfg = DFGIcator::new()
+                        .get_dfg(
+                            &ModuleInfo::default(),
+                            &operators,
+                            &bb,
+                            &vec![PrimitiveTypeInfo::I32],
+                        )
+                        .unwrap();
+
+                    // Both `1 + 2` adds should share a fingerprint. In entry
+                    // order the adds are `1 + 2`, `1 + 2`, their sum, `1 + 3`
+                    // and the final sum.
+                    let add_fingerprints: Vec<u128> = dfg
+                        .entries
+                        .iter()
+                        .filter(|e| matches!(e.operator, super::StackType::IndexAtCode(_, 2)))
+                        .map(|e| e.fingerprint)
+                        .collect();
+                    assert!(add_fingerprints.len() >= 4);
+                    assert_eq!(add_fingerprints[0], add_fingerprints[1]);
+                    assert_ne!(add_fingerprints[0], add_fingerprints[2]);
+                    assert_ne!(add_fingerprints[0], add_fingerprints[3]);
+
+                    let bucket = dfg.fingerprints.get(&add_fingerprints[0]).unwrap();
+                    assert!(bucket.len() >= 2);
+                }
+                wasmparser::Payload::End => {
+                    break;
+                }
+                _ => {
+                    // Do nothing
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dfgicator_recycles_scratch_buffers() {
+        // Recycling a `MiniDFG` should hand its buffers back to the
+        // `DFGIcator`'s scratch pool so a second `get_dfg` call on the same
+        // `DFGIcator` reuses that allocation and still produces an
+        // identical-shaped DFG.
+        let original = &wat::parse_str(
+            r#"
+        (module
+            (memory 1)
+            (func (export "exported_func") (param i32) (result i32)
+                local.get 0
+                i32.const 1
+                i32.add
+            )
+        )
+        "#,
+        )
+        .unwrap();
+
+        let mut parser = Parser::new(0);
+        let mut consumed = 0;
+        loop {
+            let (payload, size) = match parser.parse(&original[consumed..], true).unwrap() {
+                wasmparser::Chunk::NeedMoreData(_) => {
+                    panic!("This should not happen")
+                }
+                wasmparser::Chunk::Parsed { consumed, payload } => (payload, consumed),
+            };
+
+            consumed += size;
+
+            match payload {
+                wasmparser::Payload::CodeSectionEntry(reader) => {
+                    let operators = reader
+                        .get_operators_reader()
+                        .unwrap()
+                        .into_iter_with_offsets()
+                        .collect::<wasmparser::Result<Vec<OperatorAndByteOffset>>>()
+                        .unwrap();
+
+                    let mut dfgicator = DFGIcator::with_capacity(8);
+                    let bb = dfgicator.get_bb_from_operator(2, &operators).unwrap();
+                    let dfg = dfgicator
+                        .get_dfg(
+                            &ModuleInfo::default(),
+                            &operators,
+                            &bb,
+                            &vec![PrimitiveTypeInfo::I32],
+                        )
+                        .unwrap();
+                    let entry_count = dfg.entries.len();
+                    let capacity = dfg.entries.capacity();
+                    dfgicator.recycle(dfg);
+
+                    // The recycled buffer's capacity should still be around,
+                    // so the second build doesn't need to allocate a new one.
+                    assert!(dfgicator.scratch.dfg_map.capacity() >= capacity);
+
+                    let dfg2 = dfgicator
+                        .get_dfg(
+                            &ModuleInfo::default(),
+                            &operators,
+                            &bb,
+                            &vec![PrimitiveTypeInfo::I32],
+                        )
+                        .unwrap();
+                    assert_eq!(dfg2.entries.len(), entry_count);
+                }
+                wasmparser::Payload::End => {
+                    break;
+                }
+                _ => {
+                    // Do nothing
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dfg_generated_operators() {
+        // `i32.clz` and `f32.add` only gained DFG support through
+        // `operators.in`; previously they'd hit the `_ => return None` arm.
+        let original = &wat::parse_str(
+            r#"
+        (module
+            (memory 1)
+            (func (export "exported_func") (param i32 f32) (result i32)
+                local.get 0
+                i32.clz
+                local.get 1
+                local.get 1
+                f32.add
+                drop
+            )
+        )
+        "#,
+        )
+        .unwrap();
+
+        let mut parser = Parser::new(0);
+        let mut consumed = 0;
+        loop {
+            let (payload, size) = match parser.parse(&original[consumed..], true).unwrap() {
+                wasmparser::Chunk::NeedMoreData(_) => {
+                    panic!("This should not happen")
+                }
+                wasmparser::Chunk::Parsed { consumed, payload } => (payload, consumed),
+            };
+
+            consumed += size;
+
+            match payload {
+                wasmparser::Payload::CodeSectionEntry(reader) => {
+                    let operators = reader
+                        .get_operators_reader()
+                        .unwrap()
+                        .into_iter_with_offsets()
+                        .collect::<wasmparser::Result<Vec<OperatorAndByteOffset>>>()
+                        .unwrap();
+
+                    let bb = DFGIcator::new()
+                        .get_bb_from_operator(5, &operators)
+                        .unwrap();
+                    let dfg = DFGIcator::new()
+                        .get_dfg(
+                            &ModuleInfo::default(),
+                            &operators,
+                            &bb,
+                            &vec![PrimitiveTypeInfo::I32, PrimitiveTypeInfo::F32],
+                        )
+                        .unwrap();
+
+                    assert!(dfg
+                        .entries
+                        .iter()
+                        .any(|e| matches!(e.operator, super::StackType::IndexAtCode(_, 1))
+                            && matches!(e.return_type, PrimitiveTypeInfo::I32)));
+                    assert!(dfg
+                        .entries
+                        .iter()
+                        .any(|e| matches!(e.operator, super::StackType::IndexAtCode(_, 2))
+                            && matches!(e.return_type, PrimitiveTypeInfo::F32)));
+                }
+                wasmparser::Payload::End => {
+                    break;
+                }
+                _ => {
+                    // Do nothing
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_function_dfg_phi_at_if_else_join() {
+        // The `if`/`else` arms push different constants, so the value on top
+        // of the stack right after `end` must be a `StackType::Phi` merging
+        // both arms; `i32.add` then consumes it like any other entry.
+        let original = &wat::parse_str(
+            r#"
+        (module
+            (func (export "exported_func") (param i32) (result i32)
+                local.get 0
+                if (result i32)
+                    i32.const 1
+                else
+                    i32.const 2
+                end
+                i32.const 3
+                i32.add
+            )
+        )
+        "#,
+        )
+        .unwrap();
+
+        let mut parser = Parser::new(0);
+        let mut consumed = 0;
+        loop {
+            let (payload, size) = match parser.parse(&original[consumed..], true).unwrap() {
+                wasmparser::Chunk::NeedMoreData(_) => {
+                    panic!("This should not happen")
+                }
+                wasmparser::Chunk::Parsed { consumed, payload } => (payload, consumed),
+            };
+
+            consumed += size;
+
+            match payload {
+                wasmparser::Payload::CodeSectionEntry(reader) => {
+                    let operators = reader
+                        .get_operators_reader()
+                        .unwrap()
+                        .into_iter_with_offsets()
+                        .collect::<wasmparser::Result<Vec<OperatorAndByteOffset>>>()
+                        .unwrap();
+
+                    let dfg = DFGIcator::new()
+                        .get_function_dfg(
+                            &ModuleInfo::default(),
+                            &operators,
+                            &vec![PrimitiveTypeInfo::I32],
+                        )
+                        .unwrap();
+
+                    let phi = dfg
+                        .entries
+                        .iter()
+                        .find(|e| matches!(e.operator, super::StackType::Phi))
+                        .expect("if/else join should produce a Phi entry");
+                    assert_eq!(phi.operands.len(), 2);
+                    assert!(phi
+                        .operands
+                        .iter()
+                        .all(|&op| matches!(dfg.entries[op].operator, super::StackType::I32(_))));
+                    assert!(!dfg.block_roots.is_empty());
+                }
+                wasmparser::Payload::End => {
+                    break;
+                }
+                _ => {
+                    // Do nothing
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dfg_to_dot() {
+        let original = &wat::parse_str(
+            r#"
+        (module
+            (func (export "exported_func") (param i32) (result i32)
+                local.get 0
+                local.get 0
+                i32.add
+            )
+        )
+        "#,
+        )
+        .unwrap();
+
+        let mut parser = Parser::new(0);
+        let mut consumed = 0;
+        loop {
+            let (payload, size) = match parser.parse(&original[consumed..], true).unwrap() {
+                wasmparser::Chunk::NeedMoreData(_) => {
+                    panic!("This should not happen")
+                }
+                wasmparser::Chunk::Parsed { consumed, payload } => (payload, consumed),
+            };
+
+            consumed += size;
+
+            match payload {
+                wasmparser::Payload::CodeSectionEntry(reader) => {
+                    let operators = reader
+                        .get_operators_reader()
+                        .unwrap()
+                        .into_iter_with_offsets()
+                        .collect::<wasmparser::Result<Vec<OperatorAndByteOffset>>>()
+                        .unwrap();
+
+                    let roots = DFGIcator::new().get_bb_from_operator(2, &operators).unwrap();
+                    let dfg = DFGIcator::new()
+                        .get_dfg(
+                            &ModuleInfo::default(),
+                            &operators,
+                            &roots,
+                            &vec![PrimitiveTypeInfo::I32],
+                        )
+                        .unwrap();
+
+                    let dot = dfg.to_dot(&operators);
+                    assert!(dot.starts_with("digraph DFG {"));
+                    assert!(dot.contains("->"));
+                    assert_eq!(dot.matches("label=").count(), dfg.entries.len());
+                }
+                wasmparser::Payload::End => {
+                    break;
+                }
+                _ => {
+                    // Do nothing
+                }
+            }
+        }
+    }
 }
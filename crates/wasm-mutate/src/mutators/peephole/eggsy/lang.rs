@@ -13,11 +13,35 @@ define_language! {
         "xor" = Xor([Id; 2]),
         "shl" = Shl([Id; 2]),
         "shr_u" = ShrU([Id; 2]),
+        "shr_s" = ShrS([Id; 2]),
+        "rotl" = Rotl([Id; 2]),
+        "rotr" = Rotr([Id; 2]),
+        "div_u" = DivU([Id; 2]),
+        "div_s" = DivS([Id; 2]),
+        "rem_u" = RemU([Id; 2]),
+        "rem_s" = RemS([Id; 2]),
         "popcnt" = Popcnt(Id),
+        "clz" = Clz(Id),
+        "ctz" = Ctz(Id),
         "drop" = Drop,
+        // Comparisons
+        "eq" = Eq([Id; 2]),
+        "ne" = Ne([Id; 2]),
+        "lt_u" = LtU([Id; 2]),
+        "lt_s" = LtS([Id; 2]),
+        "gt_u" = GtU([Id; 2]),
+        "gt_s" = GtS([Id; 2]),
+        "le_u" = LeU([Id; 2]),
+        "le_s" = LeS([Id; 2]),
+        "ge_u" = GeU([Id; 2]),
+        "ge_s" = GeS([Id; 2]),
+        "eqz" = Eqz(Id),
+        // `select` takes the condition first, then the two values, mirroring
+        // the order `Encoder` pops them off the virtual stack.
+        "select" = Select([Id; 3]),
         // Memory operations
         "load" = ILoad(Id),
-        // TODO add the others
+        "store" = IStore([Id; 2]),
 
         // Custom mutation operations and instructions
         //
@@ -33,6 +57,13 @@ define_language! {
             Takes one constant operand and turn it into a sum of two random numbers whihch sum is the operand `i32.const x = i32.const r + i32.const (x - r) `
         */
         "unfold" = Unfold(Id),
+        /*
+            A call to an outlined function (see `peephole::outlining`): the
+            first child is the callee's function index, encoded as a `Num`
+            leaf the same way other literal indices are in this language,
+            and the rest are its arguments in left-to-right evaluation order.
+        */
+        "call" = Call(Box<[Id]>),
         // End of custom mutation operations and instructions
 
         Num(i64),
@@ -1,10 +1,16 @@
-use std::{cmp::Ordering, collections::HashMap, num::Wrapping};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    num::Wrapping,
+    sync::Mutex,
+};
 
 use egg::{define_language, Analysis, CostFunction, EClass, EGraph, Id, Language, RecExpr, Symbol};
 use rand::{
     prelude::{SliceRandom, SmallRng},
-    Rng,
+    Rng, SeedableRng,
 };
+use rayon::prelude::*;
 use wasm_encoder::{Function, Instruction};
 use wasmparser::Operator;
 
@@ -37,6 +43,55 @@ fn cmp<T: PartialOrd>(a: &Option<T>, b: &Option<T>) -> Ordering {
     }
 }
 
+/// Free-function version of a cost pass over one e-class, taking its
+/// `cost_function` and a `costs` snapshot by reference instead of through
+/// `&mut self`, so `find_costs`'s parallel batches can each hand it a
+/// private `CF` instance and a read-only snapshot rather than contending on
+/// `self`.
+fn make_pass<CF, L, N>(
+    egraph: &EGraph<L, N>,
+    cost_function: &mut CF,
+    costs: &HashMap<Id, (CF::Cost, usize)>,
+    eclass: &EClass<L, N::Data>,
+) -> Option<(CF::Cost, usize)>
+where
+    CF: CostFunction<L>,
+    L: Language,
+    N: Analysis<L>,
+{
+    let (cost, node_idx) = eclass
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (node_total_cost(egraph, cost_function, costs, n), i))
+        .min_by(|a, b| cmp(&a.0, &b.0))
+        .unwrap_or_else(|| panic!("Can't extract, eclass is empty: {:#?}", eclass));
+    cost.map(|c| (c, node_idx))
+}
+
+/// Free-function counterpart to [`RandomExtractor::node_cost`] used by
+/// `find_costs`'s parallel passes (see `make_pass`) before `self.costs` has
+/// reached a fixpoint, so it takes the in-progress `costs` snapshot as a
+/// parameter rather than reading `self.costs`.
+fn node_total_cost<CF, L, N>(
+    egraph: &EGraph<L, N>,
+    cost_function: &mut CF,
+    costs: &HashMap<Id, (CF::Cost, usize)>,
+    node: &L,
+) -> Option<CF::Cost>
+where
+    CF: CostFunction<L>,
+    L: Language,
+    N: Analysis<L>,
+{
+    let has_cost = |&id: &Id| costs.contains_key(&egraph.find(id));
+    if node.children().iter().all(has_cost) {
+        let cost_f = |id: Id| costs[&egraph.find(id)].0.clone();
+        Some(cost_function.cost(node, cost_f))
+    } else {
+        None
+    }
+}
+
 impl<'a, CF, L, N> RandomExtractor<'a, CF, L, N>
 where
     CF: CostFunction<L>,
@@ -44,7 +99,13 @@ where
     N: Analysis<L, Data = Option<i32>>, // The analysis should return the index of the node in the e-class
 {
     /// Returns a new Random extractor from an egraph and a custom cost function
-    pub fn new(egraph: &'a EGraph<L, N>, cost_function: CF) -> Self {
+    pub fn new(egraph: &'a EGraph<L, N>, cost_function: CF) -> Self
+    where
+        CF: Clone + Sync,
+        L: Sync,
+        N: Sync,
+        CF::Cost: Send,
+    {
         let costs = HashMap::default();
 
         let mut extractor = RandomExtractor {
@@ -56,60 +117,65 @@ where
         extractor
     }
 
-    fn find_costs(&mut self) -> HashMap<Id, (CF::Cost, usize)> {
-        let mut costs = HashMap::new();
+    /// Runs the cost fixpoint to completion, same as the sequential version,
+    /// but processes each pass's e-classes in parallel worklist batches via
+    /// rayon: every class in a batch is costed against a read-only snapshot
+    /// of `costs` taken at the start of that batch, and only the merge of an
+    /// improved cost back into the shared map takes a lock. Batch size is
+    /// `classes / threads`, floored at 1, over the e-graph's full class
+    /// count: every pass revisits every e-class, not only those whose cost
+    /// may still change, so the batch size is the same from pass to pass.
+    ///
+    /// Requires `CF: Clone` so each batch can own a private cost-function
+    /// instance: `CostFunction::cost` takes `&mut self`, so sharing one
+    /// instance across threads would need its own lock and serialize the
+    /// very work we're trying to parallelize.
+    fn find_costs(&mut self) -> HashMap<Id, (CF::Cost, usize)>
+    where
+        CF: Clone + Sync,
+        L: Sync,
+        N: Sync,
+        CF::Cost: Send,
+    {
+        let egraph = self.egraph;
+        let costs: Mutex<HashMap<Id, (CF::Cost, usize)>> = Mutex::new(HashMap::new());
 
         let mut did_something = true;
         while did_something {
-            did_something = false;
-
-            for class in self.egraph.classes() {
-                let pass = self.make_pass(&mut costs, class);
-                match (costs.get(&class.id), pass) {
-                    (None, Some(new)) => {
-                        costs.insert(class.id, new);
-                        did_something = true;
+            let classes: Vec<_> = egraph.classes().collect();
+            let num_threads = rayon::current_num_threads().max(1);
+            let batch_size = (classes.len() / num_threads).max(1);
+
+            did_something = classes
+                .par_chunks(batch_size)
+                .map(|batch| {
+                    let mut cost_function = self.cost_function.clone();
+                    let mut batch_did_something = false;
+                    let snapshot = costs.lock().unwrap().clone();
+
+                    for class in batch {
+                        let pass = make_pass(egraph, &mut cost_function, &snapshot, class);
+
+                        let mut costs = costs.lock().unwrap();
+                        match (costs.get(&class.id), pass) {
+                            (None, Some(new)) => {
+                                costs.insert(class.id, new);
+                                batch_did_something = true;
+                            }
+                            (Some(old), Some(new)) if new.0 < old.0 => {
+                                costs.insert(class.id, new);
+                                batch_did_something = true;
+                            }
+                            _ => (),
+                        }
                     }
-                    (Some(old), Some(new)) if new.0 < old.0 => {
-                        costs.insert(class.id, new);
-                        did_something = true;
-                    }
-                    _ => (),
-                }
-            }
-        }
-
-        costs
-    }
 
-    fn make_pass(
-        &mut self,
-        costs: &mut HashMap<Id, (CF::Cost, usize)>,
-        eclass: &EClass<L, Option<i32>>,
-    ) -> Option<(CF::Cost, usize)> {
-        let (cost, node_idx) = eclass
-            .iter()
-            .enumerate()
-            .map(|(i, n)| (self.node_total_cost(n, costs), i))
-            .min_by(|a, b| cmp(&a.0, &b.0))
-            .unwrap_or_else(|| panic!("Can't extract, eclass is empty: {:#?}", eclass));
-        cost.map(|c| (c, node_idx))
-    }
-
-    fn node_total_cost(
-        &mut self,
-        node: &L,
-        costs: &mut HashMap<Id, (CF::Cost, usize)>,
-    ) -> Option<CF::Cost> {
-        let egraph = self.egraph;
-        let has_cost = |&id| costs.contains_key(&egraph.find(id));
-        if node.children().iter().all(has_cost) {
-            let costs = &costs;
-            let cost_f = |id| costs[&egraph.find(id)].0.clone();
-            Some(self.cost_function.cost(&node, cost_f))
-        } else {
-            None
+                    batch_did_something
+                })
+                .reduce(|| false, |a, b| a || b);
         }
+
+        costs.into_inner().unwrap()
     }
 
     /// The the cost of the egraph nodes
@@ -187,6 +253,376 @@ where
             &operands,
         ))
     }
+
+    /// Runs `extract_random` once per seed in `seeds`, in parallel via
+    /// rayon, reusing the single `costs` fixpoint `new` already computed
+    /// instead of recomputing it per candidate. This is the entry point for
+    /// fuzzing/superoptimization callers that want thousands of candidates
+    /// out of one e-graph: the expensive part (`find_costs`) is paid once,
+    /// and each seed's traversal is independent of every other's (its own
+    /// `SmallRng`, its own `id_to_node`/`operands` buffers), so there's
+    /// nothing to lock here — unlike `find_costs`, which does.
+    ///
+    /// The result is deduplicated, since distinct seeds commonly land on the
+    /// same concrete tree (e.g. whenever every e-class they visit happens to
+    /// have few nodes); a caller sampling thousands of seeds wants the
+    /// distinct candidates that produced, not that many duplicate copies of
+    /// the same few trees.
+    pub fn extract_random_batch(
+        &self,
+        seeds: &[u64],
+        eclass: Id,
+        max_depth: u32,
+        encoder: impl Fn(Id, &Vec<&L>, &Vec<Vec<Id>>) -> RecExpr<L> + Sync,
+    ) -> crate::Result<Vec<RecExpr<L>>>
+    where
+        CF: Sync,
+        CF::Cost: Sync,
+        L: Sync,
+        N: Sync,
+        RecExpr<L>: Eq + std::hash::Hash,
+    {
+        let candidates = seeds
+            .par_iter()
+            .map(|&seed| {
+                let mut rnd = SmallRng::seed_from_u64(seed);
+                self.extract_random(&mut rnd, eclass, max_depth, &encoder)
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        let mut seen = HashSet::new();
+        Ok(candidates
+            .into_iter()
+            .filter(|candidate| seen.insert(candidate.clone()))
+            .collect())
+    }
+
+    /// Does the same pre-order traversal as `extract_random`, but at every
+    /// e-class deterministically takes `self.costs[&id].1`, the cheapest
+    /// e-node the cost fixpoint in `find_costs` found, instead of a random
+    /// pick. This reconstructs the single globally lowest-cost `RecExpr<L>`
+    /// rooted at `eclass`, mirroring egg's own `Extractor::find_best`.
+    ///
+    /// Unlike `extract_random` there is no `max_depth` cutoff: `self.costs`
+    /// is already a fixpoint over the whole e-graph, so every reachable
+    /// e-class has a finite cheapest node and the traversal is guaranteed to
+    /// terminate on its own.
+    pub fn extract_best(
+        &self,
+        eclass: Id,
+        encoder: impl Fn(Id, &Vec<&L>, &Vec<Vec<Id>>) -> RecExpr<L>,
+    ) -> crate::Result<RecExpr<L>> {
+        // A map from a node's id to its actual node data.
+        let mut id_to_node = vec![];
+        // A map from a parent node id to its child operand node ids.
+        let mut operands = vec![];
+
+        let rootidx = self.costs[&eclass].1;
+        let rootnode = &self.egraph[eclass].nodes[rootidx];
+
+        id_to_node.push(&self.egraph[eclass].nodes[rootidx]);
+        operands.push(vec![]);
+
+        let mut worklist: Vec<_> = rootnode
+            .children()
+            .iter()
+            .rev()
+            .map(|id| (eclass, 0, id))
+            .collect();
+
+        while let Some((_parent, parentidx, &node)) = worklist.pop() {
+            let node_idx = self.costs[&node].1;
+
+            let operand = Id::from(id_to_node.len());
+            let operandidx = id_to_node.len();
+            let last_node_id = parentidx;
+
+            id_to_node.push(&self.egraph[node].nodes[node_idx]);
+            operands.push(vec![]);
+
+            operands[last_node_id].push(operand);
+
+            worklist.extend(
+                self.egraph[node].nodes[node_idx]
+                    .children()
+                    .iter()
+                    .rev()
+                    .map(|id| (operand, operandidx, id)),
+            );
+        }
+        // Build the tree with the right language constructor
+        Ok(encoder(
+            Id::from(0), /* The root of the expr is the node at position 0 */
+            &id_to_node,
+            &operands,
+        ))
+    }
+
+    /// Like `node_total_cost`, but reads children costs straight out of the
+    /// already-complete `self.costs` fixpoint instead of a transient local
+    /// map, so it can be used to cost arbitrary (not just cheapest) nodes
+    /// after `find_costs` has run.
+    fn node_cost(&mut self, node: &L) -> Option<CF::Cost> {
+        let egraph = self.egraph;
+        let has_cost = |&id: &Id| self.costs.contains_key(&egraph.find(id));
+        if node.children().iter().all(has_cost) {
+            let costs = &self.costs;
+            let cost_f = |id: Id| costs[&egraph.find(id)].0.clone();
+            Some(self.cost_function.cost(node, cost_f))
+        } else {
+            None
+        }
+    }
+
+    /// Like `extract_random`, but instead of choosing uniformly at random
+    /// among an e-class's nodes, weights the choice by the Boltzmann factor
+    /// `exp(-cost_i / temperature)` of each node's `self.cost_function` cost,
+    /// so cheaper nodes are more likely to be picked. `temperature ->
+    /// infinity` makes every weight converge to the same value, recovering
+    /// `extract_random`'s uniform behavior; `temperature -> 0` concentrates
+    /// almost all the probability on the single cheapest node, approaching
+    /// `extract_best`.
+    ///
+    /// `CF::Cost: Into<f64>` is required here, rather than on the whole
+    /// `impl` block, since only the weighting itself needs a float to raise
+    /// `exp` to.
+    pub fn extract_random_weighted(
+        &mut self,
+        rnd: &mut rand::prelude::SmallRng,
+        eclass: Id,
+        temperature: f64,
+        max_depth: u32,
+        encoder: impl Fn(Id, &Vec<&L>, &Vec<Vec<Id>>) -> RecExpr<L>,
+    ) -> crate::Result<RecExpr<L>>
+    where
+        CF::Cost: Into<f64>,
+    {
+        // A map from a node's id to its actual node data.
+        let mut id_to_node = vec![];
+        // A map from a parent node id to its child operand node ids.
+        let mut operands = vec![];
+
+        let rootidx = self.weighted_node_choice(rnd, eclass, temperature);
+        let rootnode = &self.egraph[eclass].nodes[rootidx];
+
+        id_to_node.push(&self.egraph[eclass].nodes[rootidx]);
+        operands.push(vec![]);
+
+        let mut worklist: Vec<_> = rootnode
+            .children()
+            .iter()
+            .rev()
+            .map(|id| (eclass, 0, id, 0))
+            .collect();
+
+        while let Some((parent, parentidx, &node, depth)) = worklist.pop() {
+            let node_idx = if depth >= max_depth {
+                // look nearest leaf path, in this case, the best in AST size
+                self.costs[&node].1
+            } else {
+                self.weighted_node_choice(rnd, node, temperature)
+            };
+
+            let operand = Id::from(id_to_node.len());
+            let operandidx = id_to_node.len();
+            let last_node_id = parentidx;
+
+            id_to_node.push(&self.egraph[node].nodes[node_idx]);
+            operands.push(vec![]);
+
+            operands[last_node_id].push(operand);
+
+            worklist.extend(
+                self.egraph[node].nodes[node_idx]
+                    .children()
+                    .iter()
+                    .rev()
+                    .map(|id| (operand, operandidx, id, depth + 1)),
+            );
+        }
+        // Build the tree with the right language constructor
+        Ok(encoder(
+            Id::from(0), /* The root of the expr is the node at position 0 */
+            &id_to_node,
+            &operands,
+        ))
+    }
+
+    /// Picks a node index within `eclass` with probability proportional to
+    /// `exp(-cost / temperature)`. If the weights don't add up to a finite,
+    /// positive total (every node's cost is unknown, or a weight came out
+    /// `inf`/`NaN`), it picks the node with the lowest raw cost instead, or
+    /// node `0` if no node's cost is known.
+    fn weighted_node_choice(
+        &mut self,
+        rnd: &mut rand::prelude::SmallRng,
+        eclass: Id,
+        temperature: f64,
+    ) -> usize
+    where
+        CF::Cost: Into<f64>,
+    {
+        let nodes_len = self.egraph[eclass].nodes.len();
+        let weights: Vec<f64> = (0..nodes_len)
+            .map(|i| {
+                let node = &self.egraph[eclass].nodes[i];
+                self.node_cost(node)
+                    .map(|cost| (-(cost.into()) / temperature).exp())
+                    .unwrap_or(0.0)
+            })
+            .collect();
+
+        let total: f64 = weights.iter().sum();
+        if !total.is_finite() || total <= 0.0 {
+            // Every weight is `0.0` (cost unknown for every node, which
+            // shouldn't happen in practice), or some weight over/underflowed
+            // to `inf`/`NaN` (e.g. `temperature -> 0` with a `0`-cost node,
+            // where `(-0.0 / 0.0).exp()` is `NaN`): fall back to the
+            // cheapest node by raw cost directly, matching "temperature ->
+            // 0 approaches `extract_best`" above, or node 0 if no node's
+            // cost is known yet.
+            let nodes: Vec<L> = self.egraph[eclass].nodes.to_vec();
+            return nodes
+                .iter()
+                .enumerate()
+                .filter_map(|(i, node)| Some((i, self.node_cost(node)?.into())))
+                .min_by(|(_, a): &(usize, f64), (_, b): &(usize, f64)| a.total_cmp(b))
+                .map_or(0, |(i, _)| i);
+        }
+
+        let mut x = rnd.gen_range(0.0, total);
+        for (i, &w) in weights.iter().enumerate() {
+            if x < w {
+                return i;
+            }
+            x -= w;
+        }
+        nodes_len - 1
+    }
+}
+
+/// The number of bytes a `memarg` (`align`, `offset`, both encoded as LEB128
+/// `u32`s) adds to a memory instruction, in the common case where both fit in
+/// a single byte.
+const MEMARG_BYTES: usize = 2;
+
+/// The length, in bytes, of `value`'s signed LEB128 encoding, i.e. the same
+/// encoding `i32.const`/`i64.const`'s immediate uses.
+fn signed_leb128_len(value: i64) -> usize {
+    let mut value = value;
+    let mut len = 0;
+    loop {
+        len += 1;
+        let byte = value & 0x7f;
+        value >>= 7;
+        if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+            break;
+        }
+    }
+    len
+}
+
+/// The length, in bytes, of `value`'s unsigned LEB128 encoding, i.e. the same
+/// encoding a `local.get`/`local.set` index uses.
+fn unsigned_leb128_len(value: u32) -> usize {
+    let mut value = value;
+    let mut len = 1;
+    while value >= 0x80 {
+        value >>= 7;
+        len += 1;
+    }
+    len
+}
+
+/// A [`CostFunction<Lang>`] whose `Cost` is the number of bytes the node
+/// would encode to as real Wasm bytecode, rather than `egg::AstSize`'s
+/// undifferentiated AST-node count. Feeding this to
+/// [`RandomExtractor::extract_best`] biases extraction toward code that's
+/// actually smaller on the wire: an `i32.const 1000000` and an `i32.const 0`
+/// have the same AST size but very different LEB128-encoded lengths.
+pub struct EncodedSizeCostFn;
+
+impl CostFunction<Lang> for EncodedSizeCostFn {
+    type Cost = usize;
+
+    fn cost<C>(&mut self, enode: &Lang, mut costs: C) -> Self::Cost
+    where
+        C: FnMut(Id) -> Self::Cost,
+    {
+        // `call`'s function-index child is a bare `Num` leaf rather than a
+        // real `i32.const` (see `Lang::Call`'s doc comment), so charging it
+        // through the generic fold below like any other child would double
+        // up on an opcode byte: one for `call` itself, another from `Num`'s
+        // own cost arm for an `i32.const` that was never actually emitted.
+        // Only its LEB128 length belongs to `call`'s own encoded size.
+        if let Lang::Call(ids) = enode {
+            let index_cost = ids.first().map_or(0, |&id| costs(id).saturating_sub(1));
+            let args_cost = ids.iter().skip(1).fold(0, |sum, &id| sum + costs(id));
+            return 1 + index_cost + args_cost;
+        }
+
+        let self_cost = match enode {
+            // A binary arithmetic/bitwise instruction is a single opcode byte.
+            Lang::Add(_)
+            | Lang::Sub(_)
+            | Lang::Mul(_)
+            | Lang::And(_)
+            | Lang::Or(_)
+            | Lang::Xor(_)
+            | Lang::Shl(_)
+            | Lang::ShrU(_)
+            | Lang::ShrS(_)
+            | Lang::Rotl(_)
+            | Lang::Rotr(_)
+            | Lang::DivU(_)
+            | Lang::DivS(_)
+            | Lang::RemU(_)
+            | Lang::RemS(_) => 1,
+            // So are `drop`, `popcnt`, `clz` and `ctz`.
+            Lang::Drop | Lang::Popcnt(_) | Lang::Clz(_) | Lang::Ctz(_) => 1,
+            // Comparisons and `select` are also single opcode bytes (`select`
+            // has no immediate in the MVP encoding).
+            Lang::Eq(_)
+            | Lang::Ne(_)
+            | Lang::LtU(_)
+            | Lang::LtS(_)
+            | Lang::GtU(_)
+            | Lang::GtS(_)
+            | Lang::LeU(_)
+            | Lang::LeS(_)
+            | Lang::GeU(_)
+            | Lang::GeS(_)
+            | Lang::Eqz(_)
+            | Lang::Select(_) => 1,
+            // `load`/`store`'s opcode byte plus their memarg.
+            Lang::ILoad(_) | Lang::IStore(_) => 1 + MEMARG_BYTES,
+            // `i32.const`/`i64.const`'s opcode byte plus the immediate's
+            // signed LEB128 encoding.
+            Lang::Num(v) => 1 + signed_leb128_len(*v),
+            // Handled above, before this match, since it needs to special-case
+            // its first child's cost rather than just fold over all of them.
+            Lang::Call(_) => unreachable!("Lang::Call returns early above"),
+            // Pure e-graph bookkeeping, not real instructions: these don't
+            // encode to any bytes of their own.
+            Lang::Rand | Lang::Undef | Lang::Unfold(_) => 0,
+            // A `local.get`, whose numeric index is packed into the egg
+            // symbol's name; fall back to a single byte if it isn't
+            // (e.g. a rewrite-rule pattern variable) since every local
+            // index still needs its own LEB128 byte at minimum.
+            Lang::Symbol(s) => {
+                1 + s
+                    .as_str()
+                    .parse::<u32>()
+                    .map(unsigned_leb128_len)
+                    .unwrap_or(1)
+            }
+        };
+
+        enode
+            .children()
+            .iter()
+            .fold(self_cost, |sum, &id| sum + costs(id))
+    }
 }
 
 #[cfg(test)]
@@ -203,7 +639,7 @@ mod tests {
         },
         WasmMutate,
     };
-    use egg::{rewrite, AstSize, Id, Pattern, RecExpr, Rewrite, Runner, Searcher};
+    use egg::{rewrite, AstSize, CostFunction, Id, Pattern, RecExpr, Rewrite, Runner, Searcher};
     use rand::{prelude::SliceRandom, rngs::SmallRng, Rng, SeedableRng};
     use wasm_encoder::Function;
     use wasmparser::Parser;
@@ -235,6 +671,116 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn test_random_batch_extraction() {
+        let rules: &[Rewrite<Lang, PeepholeMutationAnalysis>] = &[
+            rewrite!("unfold-2";  "?x" => "(unfold ?x)"),
+        ];
+
+        let start = "?x".parse().unwrap();
+        let runner = Runner::default().with_expr(&start).run(rules);
+        let mut egraph = runner.egraph;
+        let cf = AstSize;
+
+        // ?x is the root
+        let root = egraph.add_expr(&start);
+        let extractor = RandomExtractor::new(&egraph, cf);
+        let encoder = Encoder::build_expr;
+
+        let seeds: Vec<u64> = (0..16).collect();
+        let candidates = extractor
+            .extract_random_batch(&seeds, root, 10, encoder)
+            .unwrap();
+
+        // At most one candidate per seed, and every one of them is a valid
+        // extraction of the same e-class, so it must be either `?x` or
+        // `(unfold ?x)` (the only two nodes `root`'s e-class has).
+        assert!(!candidates.is_empty());
+        assert!(candidates.len() <= seeds.len());
+        for candidate in &candidates {
+            assert!(candidate == &start || candidate.as_ref().len() == 2);
+        }
+    }
+
+    #[test]
+    fn test_best_extraction() {
+        let rules: &[Rewrite<Lang, PeepholeMutationAnalysis>] = &[
+            rewrite!("unfold-2";  "?x" => "(unfold ?x)"),
+        ];
+
+        let start = "?x".parse().unwrap();
+        let runner = Runner::default().with_expr(&start).run(rules);
+        let mut egraph = runner.egraph;
+        let cf = AstSize;
+
+        // ?x is the root
+        let root = egraph.add_expr(&start);
+        let extractor = RandomExtractor::new(&egraph, cf);
+        let encoder = Encoder::build_expr;
+
+        // `?x` itself is cheaper than `(unfold ?x)`, so the best extraction
+        // should always pick it regardless of how many times this is called.
+        for _ in 0..5 {
+            let expr = extractor.extract_best(root, encoder).unwrap();
+            assert_eq!(expr, start);
+        }
+    }
+
+    /// A trivial `AstSize`-alike whose `Cost` is `f64`, since `extract_random_weighted`
+    /// needs `CF::Cost: Into<f64>` and `egg::AstSize`'s `usize` cost doesn't
+    /// implement that conversion.
+    #[derive(Clone)]
+    struct F64AstSize;
+
+    impl CostFunction<Lang> for F64AstSize {
+        type Cost = f64;
+
+        fn cost<C>(&mut self, enode: &Lang, mut costs: C) -> f64
+        where
+            C: FnMut(Id) -> f64,
+        {
+            enode.children().iter().fold(1.0, |sum, &id| sum + costs(id))
+        }
+    }
+
+    #[test]
+    fn test_weighted_extraction_prefers_cheaper_nodes_at_low_temperature() {
+        let rules: &[Rewrite<Lang, PeepholeMutationAnalysis>] =
+            &[rewrite!("unfold-2";  "?x" => "(unfold ?x)")];
+
+        let start = "?x".parse().unwrap();
+        let runner = Runner::default().with_expr(&start).run(rules);
+        let mut egraph = runner.egraph;
+        let mut rnd = SmallRng::seed_from_u64(4);
+
+        let root = egraph.add_expr(&start);
+        let mut extractor = RandomExtractor::new(&egraph, F64AstSize);
+        let encoder = Encoder::build_expr;
+
+        // `?x` is strictly cheaper than `(unfold ?x)`, so a low enough
+        // temperature should pick it (almost) every time.
+        for _ in 0..20 {
+            let expr = extractor
+                .extract_random_weighted(&mut rnd, root, 0.001, 10, encoder)
+                .unwrap();
+            assert_eq!(expr, start);
+        }
+    }
+
+    #[test]
+    fn test_encoded_size_cost_fn_scales_with_const_value() {
+        use super::EncodedSizeCostFn;
+
+        let mut cf = EncodedSizeCostFn;
+        let small = cf.cost(&Lang::Num(0), |_| 0);
+        let large = cf.cost(&Lang::Num(1_000_000), |_| 0);
+
+        // A tiny constant and a large one have the same AST size but
+        // different LEB128-encoded lengths, which is the whole point of this
+        // cost function over `AstSize`.
+        assert!(large > small);
+    }
+
     #[test]
     fn test_wasm2expr() {
         let original = &wat::parse_str(
@@ -275,16 +821,19 @@ mod tests {
                         .collect::<wasmparser::Result<Vec<OperatorAndByteOffset>>>()
                         .unwrap();
 
-                    let bb = DFGIcator::new()
-                        .get_bb_from_operator(4, &operators)
-                        .unwrap();
-
-                    let roots = DFGIcator::new()
+                    let mut dfgicator = DFGIcator::new();
+                    let bb = dfgicator.get_bb_from_operator(4, &operators).unwrap();
+                    let roots = dfgicator
                         .get_dfg(&operators, &bb, &vec![PrimitiveTypeInfo::I32])
                         .unwrap();
 
                     let mut exprroot = RecExpr::<Lang>::default();
                     let (_, _) = Encoder::wasm2expr(&roots, 4, &operators, &mut exprroot).unwrap();
+                    // Exercise `recycle` here too, alongside
+                    // `test_dfgicator_recycles_scratch_buffers`, so this
+                    // `roots` value (built from a real `wasm2expr` run) is
+                    // also covered by the scratch-pool's consuming API.
+                    dfgicator.recycle(roots);
                 }
                 wasmparser::Payload::End => {
                     break;
@@ -339,11 +888,9 @@ mod tests {
                         .collect::<wasmparser::Result<Vec<OperatorAndByteOffset>>>()
                         .unwrap();
 
-                    let bb = DFGIcator::new()
-                        .get_bb_from_operator(4, &operators)
-                        .unwrap();
-
-                    let roots = DFGIcator::new()
+                    let mut dfgicator = DFGIcator::new();
+                    let bb = dfgicator.get_bb_from_operator(4, &operators).unwrap();
+                    let roots = dfgicator
                         .get_dfg(&operators, &bb, &vec![PrimitiveTypeInfo::I32])
                         .unwrap();
 
@@ -364,6 +911,8 @@ mod tests {
                         &operators,
                     )
                     .unwrap();
+                    // See the `recycle` call in `test_wasm2expr` above.
+                    dfgicator.recycle(roots);
                 }
                 wasmparser::Payload::End => {
                     break;
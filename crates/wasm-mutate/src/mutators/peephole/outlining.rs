@@ -0,0 +1,459 @@
+//! Function outlining: finds the most profitable repeated subexpression
+//! across a module's peephole candidates (the `RecExpr<Lang>` trees
+//! `Encoder::wasm2expr` produces for each basic block/DFG root) and factors
+//! it out into a shared function, the way abstraction-learning tools like
+//! Stitch/DreamCoder factor a corpus of programs through a shared library.
+//!
+//! The core operation is *anti-unification*: given two trees, find the
+//! largest common shape where differing positions become numbered "holes",
+//! capped at `max_arity` holes. The resulting [`Abstraction`] is then matched
+//! against every candidate (including the original pair) to count how many
+//! call sites it would cover, and scored by [`utility`] the same way egg's
+//! `AstSize`/`EncodedSizeCostFn` score extraction candidates: bigger, more
+//! frequently repeated bodies are worth outlining; small or rare ones are
+//! not (and can make things *bigger*, since every call site still needs its
+//! own `call` plus argument-passing code).
+//!
+//! This module only discovers and describes a would-be outlining; callers
+//! are responsible for two invariants it assumes rather than enforces
+//! itself:
+//!
+//! - **Non-overlap**: `candidates` must already be disjoint call sites (e.g.
+//!   one per DFG root), since this module only ever matches a whole
+//!   candidate tree against an abstraction's root, never a sub-position
+//!   within one.
+//! - **Purity of holes**: [`is_value_producing`] is a conservative stand-in
+//!   for the DFG's `StackEntry::is_pure`/`use_count` analysis (see
+//!   `dfg::StackType::is_pure`) restricted to what `Lang` can express: it
+//!   rejects only `drop` and `store`, which produce no value, so every other
+//!   node, `call` and `load` included, can be bound to a hole.
+
+use std::collections::HashMap;
+
+use egg::{Id, Language, RecExpr, Symbol};
+
+use super::eggsy::lang::Lang;
+
+/// `true` if `node` always produces a value that can be safely hoisted into
+/// an outlined function's argument position. `Drop` (consumes a value,
+/// produces nothing) and `IStore` (writes to memory, produces nothing) are
+/// the current `Lang` nodes that don't, so a hole can never stand in for
+/// either of them or for anything built on top of them.
+fn is_value_producing(node: &Lang) -> bool {
+    !matches!(node, Lang::Drop | Lang::IStore(_))
+}
+
+/// `true` if `a` and `b` are the same concrete node: same operator and,
+/// for leaves carrying a payload (`Num`, `Symbol`), the same payload. Two
+/// nodes that are merely the same *shape* (e.g. two different `Num`s) are
+/// NOT equal here — anti-unification turns those into a hole instead.
+fn same_concrete_node(a: &Lang, b: &Lang) -> bool {
+    match (a, b) {
+        (Lang::Num(x), Lang::Num(y)) => x == y,
+        (Lang::Symbol(x), Lang::Symbol(y)) => x == y,
+        _ => {
+            std::mem::discriminant(a) == std::mem::discriminant(b)
+                && a.children().len() == b.children().len()
+        }
+    }
+}
+
+/// One node of an [`Abstraction`]'s body: either a concrete `Lang` node
+/// shared by every unified tree (with its children rewritten to index into
+/// the same `Abstraction`'s `nodes`, mirroring how `RecExpr` itself stores a
+/// flat, index-addressed tree), or a hole standing in for a position where
+/// the input trees disagreed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuNode {
+    Node(Lang),
+    Hole(usize),
+}
+
+/// The result of anti-unifying two or more trees: their largest common
+/// shape, as a flat vec of [`AuNode`]s (last entry is the root), plus how
+/// many distinct holes it has.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Abstraction {
+    pub nodes: Vec<AuNode>,
+    pub arity: usize,
+}
+
+impl Abstraction {
+    fn root(&self) -> usize {
+        self.nodes.len() - 1
+    }
+}
+
+/// Anti-unifies `a` and `b`: finds their largest common tree, with up to
+/// `max_arity` holes at the positions where they differ. Returns `None` if
+/// the trees require more than `max_arity` distinct differing positions to
+/// unify, or if every position differs right down to the root (nothing
+/// worth sharing).
+pub fn anti_unify(a: &RecExpr<Lang>, b: &RecExpr<Lang>, max_arity: usize) -> Option<Abstraction> {
+    let mut nodes = vec![];
+    // Anti-unification is a least-general-generalization: the same pair of
+    // differing subtrees seen at two different positions should become the
+    // *same* hole rather than two, both to minimize arity and so a
+    // recurring difference (e.g. `f(x, x)` vs `f(y, y)`) produces `f(?0,
+    // ?0)` instead of `f(?0, ?1)`.
+    let mut seen: HashMap<(Id, Id), usize> = HashMap::new();
+    let mut hole_cache: HashMap<(RecExpr<Lang>, RecExpr<Lang>), usize> = HashMap::new();
+    let root_a = Id::from(a.as_ref().len() - 1);
+    let root_b = Id::from(b.as_ref().len() - 1);
+    let mut holes = 0usize;
+    au_node(
+        a,
+        root_a,
+        b,
+        root_b,
+        max_arity,
+        &mut seen,
+        &mut hole_cache,
+        &mut holes,
+        &mut nodes,
+    )?;
+    // Differing roots leave a lone hole as the whole body: nothing shared.
+    if matches!(nodes.last(), Some(AuNode::Hole(_))) {
+        return None;
+    }
+    Some(Abstraction { nodes, arity: holes })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn au_node(
+    a: &RecExpr<Lang>,
+    ida: Id,
+    b: &RecExpr<Lang>,
+    idb: Id,
+    max_arity: usize,
+    seen: &mut HashMap<(Id, Id), usize>,
+    hole_cache: &mut HashMap<(RecExpr<Lang>, RecExpr<Lang>), usize>,
+    holes: &mut usize,
+    nodes: &mut Vec<AuNode>,
+) -> Option<usize> {
+    if let Some(&idx) = seen.get(&(ida, idb)) {
+        return Some(idx);
+    }
+
+    let na = &a[ida];
+    let nb = &b[idb];
+
+    let idx = if same_concrete_node(na, nb) && is_value_producing(na) {
+        let mut new_children = Vec::with_capacity(na.children().len());
+        for (&ca, &cb) in na.children().iter().zip(nb.children().iter()) {
+            new_children.push(Id::from(au_node(
+                a, ca, b, cb, max_arity, seen, hole_cache, holes, nodes,
+            )?));
+        }
+        let mut node = na.clone();
+        node.children_mut()
+            .iter_mut()
+            .zip(new_children)
+            .for_each(|(c, new)| *c = new);
+        let idx = nodes.len();
+        nodes.push(AuNode::Node(node));
+        idx
+    } else {
+        if !is_value_producing(na) || !is_value_producing(nb) {
+            return None;
+        }
+        // Key the hole on the *content* of what it would bind on each side,
+        // not on `(ida, idb)`'s tree position, so a difference that recurs
+        // verbatim at several positions (e.g. `f(x, x)` vs `f(y, y)`)
+        // collapses to a single hole instead of one per occurrence.
+        let key = (extract_subexpr(a, ida), extract_subexpr(b, idb));
+        if let Some(&idx) = hole_cache.get(&key) {
+            idx
+        } else {
+            if *holes >= max_arity {
+                return None;
+            }
+            let hole = *holes;
+            *holes += 1;
+            let idx = nodes.len();
+            nodes.push(AuNode::Hole(hole));
+            hole_cache.insert(key, idx);
+            idx
+        }
+    };
+
+    seen.insert((ida, idb), idx);
+    Some(idx)
+}
+
+/// The concrete subexpressions an [`Abstraction`] was matched against at one
+/// call site, indexed by hole number (`0..arity`) — which, since holes are
+/// numbered in the order `anti_unify`'s left-to-right walk first reaches
+/// them, is also the order those subexpressions must be evaluated in
+/// to preserve the original program's operand evaluation order.
+pub type Bindings = Vec<RecExpr<Lang>>;
+
+/// Copies the subtree rooted at `id` out of `expr` into a fresh,
+/// independently-indexed `RecExpr`.
+fn extract_subexpr(expr: &RecExpr<Lang>, id: Id) -> RecExpr<Lang> {
+    fn go(expr: &RecExpr<Lang>, id: Id, out: &mut RecExpr<Lang>) -> Id {
+        let mut node = expr[id].clone();
+        let new_children: Vec<Id> = node.children().iter().map(|&c| go(expr, c, out)).collect();
+        node.children_mut()
+            .iter_mut()
+            .zip(new_children)
+            .for_each(|(c, new)| *c = new);
+        out.add(node)
+    }
+    let mut out = RecExpr::default();
+    go(expr, id, &mut out);
+    out
+}
+
+/// Tries to match `abstraction` against the whole of `candidate` (never a
+/// sub-position of it — see the module's non-overlap invariant), returning
+/// the per-hole bindings on success.
+pub fn try_match(abstraction: &Abstraction, candidate: &RecExpr<Lang>) -> Option<Bindings> {
+    let mut bindings: Vec<Option<RecExpr<Lang>>> = vec![None; abstraction.arity];
+    let root = Id::from(candidate.as_ref().len() - 1);
+    if match_node(abstraction, abstraction.root(), candidate, root, &mut bindings) {
+        bindings.into_iter().collect()
+    } else {
+        None
+    }
+}
+
+fn match_node(
+    abstraction: &Abstraction,
+    node_idx: usize,
+    candidate: &RecExpr<Lang>,
+    cand_id: Id,
+    bindings: &mut Vec<Option<RecExpr<Lang>>>,
+) -> bool {
+    match &abstraction.nodes[node_idx] {
+        AuNode::Hole(h) => {
+            let sub = extract_subexpr(candidate, cand_id);
+            match &bindings[*h] {
+                None => {
+                    bindings[*h] = Some(sub);
+                    true
+                }
+                // A hole that recurs (see `au_node`'s `hole_cache`) must
+                // bind the same subexpression everywhere it appears.
+                Some(existing) => existing == &sub,
+            }
+        }
+        AuNode::Node(n) => {
+            let cn = &candidate[cand_id];
+            if !same_concrete_node(n, cn) {
+                return false;
+            }
+            n.children()
+                .iter()
+                .zip(cn.children().iter())
+                .all(|(&child_idx, &cand_child)| {
+                    match_node(abstraction, usize::from(child_idx), candidate, cand_child, bindings)
+                })
+        }
+    }
+}
+
+/// The cost/benefit of outlining `abstraction`, following the same shape as
+/// the byte/AST cost functions used for extraction (see
+/// `eggsy::EncodedSizeCostFn`): every call site saves `body_size - 1`
+/// (its own code, minus the one `call` instruction that replaces it), at
+/// the expense of emitting `body_size` once for the outlined function
+/// itself.
+pub fn utility(body_size: usize, num_matches: usize) -> i64 {
+    (body_size as i64 - 1) * num_matches as i64 - body_size as i64
+}
+
+/// One candidate outlining: the abstraction, every call site it matches
+/// (as an index into the input `candidates` plus that site's bindings), and
+/// its [`utility`].
+pub struct OutliningCandidate {
+    pub abstraction: Abstraction,
+    pub matches: Vec<(usize, Bindings)>,
+    pub utility: i64,
+}
+
+/// Finds the single most profitable outlining among `candidates` (each
+/// assumed to be one disjoint call site's whole expression), by
+/// anti-unifying every pair and keeping whichever resulting abstraction has
+/// the highest positive [`utility`] across all of its matches. Returns
+/// `None` if no pairwise abstraction has positive utility, i.e. outlining
+/// wouldn't shrink the module.
+pub fn find_best_outlining(candidates: &[RecExpr<Lang>], max_arity: usize) -> Option<OutliningCandidate> {
+    let mut best: Option<OutliningCandidate> = None;
+
+    for i in 0..candidates.len() {
+        for j in (i + 1)..candidates.len() {
+            let abstraction = match anti_unify(&candidates[i], &candidates[j], max_arity) {
+                Some(abstraction) => abstraction,
+                None => continue,
+            };
+
+            let matches: Vec<(usize, Bindings)> = candidates
+                .iter()
+                .enumerate()
+                .filter_map(|(k, candidate)| {
+                    try_match(&abstraction, candidate).map(|bindings| (k, bindings))
+                })
+                .collect();
+
+            // A body only used once isn't an abstraction worth keeping: it
+            // can't beat inlining it back in (see `utility`'s derivation).
+            if matches.len() < 2 {
+                continue;
+            }
+
+            let u = utility(abstraction.nodes.len(), matches.len());
+            if best.as_ref().map_or(true, |b| u > b.utility) {
+                best = Some(OutliningCandidate {
+                    abstraction,
+                    matches,
+                    utility: u,
+                });
+            }
+        }
+    }
+
+    best.filter(|candidate| candidate.utility > 0)
+}
+
+/// Materializes the outlined function's own body from `abstraction`: each
+/// `Hole(h)` becomes a `Lang::Symbol` reference to local `h`, the same
+/// index-as-symbol-name convention `EncodedSizeCostFn` assumes `Lang::Symbol`
+/// uses for `local.get`/`local.set`, since a hole becomes exactly that
+/// instruction once it's turned into a function parameter.
+pub fn build_function_body(abstraction: &Abstraction) -> RecExpr<Lang> {
+    let mut expr = RecExpr::default();
+    let mut map = vec![Id::from(0); abstraction.nodes.len()];
+    for (i, node) in abstraction.nodes.iter().enumerate() {
+        let id = match node {
+            AuNode::Hole(h) => expr.add(Lang::Symbol(Symbol::from(h.to_string()))),
+            AuNode::Node(n) => {
+                let mut n = n.clone();
+                n.children_mut()
+                    .iter_mut()
+                    .for_each(|c| *c = map[usize::from(*c)]);
+                expr.add(n)
+            }
+        };
+        map[i] = id;
+    }
+    expr
+}
+
+/// Builds the `RecExpr<Lang>` a matched call site should be rewritten to: a
+/// `Lang::Call` whose first child is `function_index` (stored as a `Num`
+/// leaf, the same way other literal indices are represented in this
+/// language) and whose remaining children are `bindings`, spliced in and
+/// kept in the same left-to-right order `try_match` bound them in —
+/// preserving the original operand evaluation order.
+pub fn build_call_site(function_index: i64, bindings: &Bindings) -> RecExpr<Lang> {
+    let mut expr = RecExpr::default();
+    let fn_id = expr.add(Lang::Num(function_index));
+    let mut children = vec![fn_id];
+    for binding in bindings {
+        children.push(splice(&mut expr, binding));
+    }
+    expr.add(Lang::Call(children.into_boxed_slice()));
+    expr
+}
+
+/// Copies every node of `src` into `dest`, remapping child ids, and returns
+/// the id `src`'s root ends up at in `dest`.
+fn splice(dest: &mut RecExpr<Lang>, src: &RecExpr<Lang>) -> Id {
+    let mut map = vec![Id::from(0); src.as_ref().len()];
+    for (i, node) in src.as_ref().iter().enumerate() {
+        let mut node = node.clone();
+        node.children_mut()
+            .iter_mut()
+            .for_each(|c| *c = map[usize::from(*c)]);
+        map[i] = dest.add(node);
+    }
+    map[src.as_ref().len() - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expr(s: &str) -> RecExpr<Lang> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_anti_unify_shares_identical_subtrees() {
+        let a = expr("(add (load 1) 2)");
+        let b = expr("(add (load 1) 3)");
+
+        let abstraction = anti_unify(&a, &b, 4).unwrap();
+        // Only the two different constants (2 vs 3) should become a hole;
+        // `(load 1)` and the `add` itself are shared.
+        assert_eq!(abstraction.arity, 1);
+    }
+
+    #[test]
+    fn test_anti_unify_reuses_hole_for_recurring_difference() {
+        let a = expr("(add 1 1)");
+        let b = expr("(add 2 2)");
+
+        // Both operands differ identically (1 vs 2) at both positions, so
+        // this should collapse to a single hole, not two.
+        let abstraction = anti_unify(&a, &b, 4).unwrap();
+        assert_eq!(abstraction.arity, 1);
+    }
+
+    #[test]
+    fn test_anti_unify_respects_max_arity() {
+        let a = expr("(add 1 3)");
+        let b = expr("(add 2 4)");
+
+        // Both positions differ and don't recur, so 2 holes are needed; a
+        // budget of 1 must fail.
+        assert!(anti_unify(&a, &b, 1).is_none());
+        assert!(anti_unify(&a, &b, 2).is_some());
+    }
+
+    #[test]
+    fn test_try_match_binds_and_rejects() {
+        let a = expr("(add (load 1) 2)");
+        let b = expr("(add (load 1) 3)");
+        let abstraction = anti_unify(&a, &b, 4).unwrap();
+
+        let bindings = try_match(&abstraction, &a).unwrap();
+        assert_eq!(bindings.len(), 1);
+        assert_eq!(bindings[0], expr("2"));
+
+        // A tree that differs inside the shared `(load 1)` (`9` vs `1`) can't match.
+        let mismatched = expr("(add (load 9) 2)");
+        assert!(try_match(&abstraction, &mismatched).is_none());
+    }
+
+    #[test]
+    fn test_find_best_outlining_picks_profitable_repeated_body() {
+        let candidates = vec![
+            expr("(add (load 1) 2)"),
+            expr("(add (load 1) 3)"),
+            expr("(add (load 1) 4)"),
+            expr("(mul 9 9)"), // unrelated, shouldn't affect the winner
+        ];
+
+        let best = find_best_outlining(&candidates, 2).unwrap();
+        assert_eq!(best.abstraction.arity, 1);
+        assert_eq!(best.matches.len(), 3);
+        assert!(best.utility > 0);
+    }
+
+    #[test]
+    fn test_build_function_body_and_call_site_round_trip() {
+        let a = expr("(add (load 1) 2)");
+        let b = expr("(add (load 1) 3)");
+        let abstraction = anti_unify(&a, &b, 4).unwrap();
+
+        let body = build_function_body(&abstraction);
+        // The hole becomes a `local.get 0` reference (`Symbol("0")`).
+        assert!(body.as_ref().iter().any(|n| matches!(n, Lang::Symbol(s) if s.as_str() == "0")));
+
+        let bindings = try_match(&abstraction, &a).unwrap();
+        let call_site = build_call_site(7, &bindings);
+        assert!(matches!(call_site.as_ref().last().unwrap(), Lang::Call(_)));
+    }
+}
@@ -0,0 +1,111 @@
+//! Type definitions for the hasher used by the hash-map/hash-set backend of
+//! [`Map`] and [`Set`].
+//!
+//! [`Map`]: crate::collections::Map
+//! [`Set`]: crate::collections::Set
+
+/// The [`BuildHasher`] used by the hash-map/hash-set backend of [`Map`] and [`Set`].
+///
+/// [`BuildHasher`]: core::hash::BuildHasher
+/// [`Map`]: crate::collections::Map
+/// [`Set`]: crate::collections::Set
+pub type RandomState = hashbrown::DefaultHashBuilder;
+
+/// A [`Hasher`] for key types that hash exactly one primitive integer (the
+/// common case for wasm-tools, which keys most of its maps by a `u32` type,
+/// function, global or memory index): the integer is already a good hash of
+/// itself, so running SipHash/foldhash over its 4 or 8 bytes is pure
+/// overhead. Based on the same technique as anymap's `TypeIdHasher`.
+///
+/// [`Hasher`]: core::hash::Hasher
+#[derive(Default)]
+pub struct IdentityHasher {
+    hash: u64,
+    #[cfg(debug_assertions)]
+    written: bool,
+}
+
+impl IdentityHasher {
+    /// Records that this hasher was written to, panicking (in debug builds
+    /// only) if it already was: this hasher is only sound for key types
+    /// that write exactly one integer, so a second write means either the
+    /// key type changed out from under us or isn't what we assumed it was.
+    fn mark_written(&mut self) {
+        #[cfg(debug_assertions)]
+        {
+            debug_assert!(!self.written, "IdentityHasher written to more than once");
+            self.written = true;
+        }
+    }
+}
+
+impl core::hash::Hasher for IdentityHasher {
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    fn write(&mut self, _bytes: &[u8]) {
+        debug_assert!(
+            false,
+            "IdentityHasher only supports hashing a single primitive integer, not raw bytes"
+        );
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.mark_written();
+        self.hash = i;
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_i8(&mut self, i: i8) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as u64)
+    }
+}
+
+/// The [`BuildHasher`] for [`IdentityHasher`].
+///
+/// [`BuildHasher`]: core::hash::BuildHasher
+pub type BuildIdentityHasher = core::hash::BuildHasherDefault<IdentityHasher>;
+
+/// A hash map keyed by a single primitive integer, using [`IdentityHasher`]
+/// instead of [`RandomState`] to skip hashing work entirely on the hot
+/// index-lookup paths (type/function/global/memory indices) this crate's
+/// maps are so often keyed by.
+///
+/// This is a separate alias rather than a change to [`Map`]'s own backend:
+/// [`IdentityHasher`] is only sound for keys that hash exactly one integer,
+/// which not every `Map<K, V>` user satisfies.
+///
+/// [`Map`]: crate::collections::Map
+pub type Map<K, V> = hashbrown::HashMap<K, V, BuildIdentityHasher>;
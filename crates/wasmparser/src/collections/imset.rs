@@ -0,0 +1,552 @@
+//! A persistent, structurally-shared set, for callers that need to fork a
+//! working set cheaply and throw most forks away.
+//!
+//! [`super::Set`] is the right choice for the common case of a set that is
+//! built up once and then queried. [`ImSet`] instead targets code like the
+//! `peephole` mutator, which wants to snapshot its current set of live
+//! values at every candidate rewrite and cheaply discard the ones that don't
+//! pan out: cloning a [`super::Set`] is `O(n)`, while cloning an [`ImSet`] is
+//! `O(1)` (an `Arc` bump), and `insert`/`remove` only allocate the path from
+//! the root down to the touched chunk, sharing the rest of the tree with
+//! every other clone.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::iter::FusedIterator;
+use core::ops::{BitAnd, BitOr, BitXor, Sub};
+
+/// The maximum number of elements held directly by a leaf, and the maximum
+/// fan-out of a branch. Kept small and `Arc`-shared: a modifying operation
+/// clones only the `CHUNK_CAPACITY`-sized array at the touched leaf plus one
+/// small array per ancestor branch on the path back to the root.
+const CHUNK_CAPACITY: usize = 16;
+
+#[derive(Debug)]
+enum Node<T> {
+    Leaf(Vec<T>),
+    /// `separators.len() + 1 == children.len()`. `separators[i]` is greater
+    /// than every element reachable through `children[i]` and no greater
+    /// than any element reachable through `children[i + 1]` (it starts out
+    /// as that child's smallest element; `remove` does not update it), so a
+    /// lookup for `x` descends into `children[i]` where `i` is the number of
+    /// separators that are `<= x`.
+    Branch {
+        separators: Vec<T>,
+        children: Vec<Arc<Node<T>>>,
+    },
+}
+
+impl<T> Node<T> {
+    fn empty_leaf() -> Arc<Node<T>> {
+        Arc::new(Node::Leaf(Vec::new()))
+    }
+}
+
+/// The result of inserting into or below a [`Node`]: either the subtree
+/// still fits in one node, or it grew past `CHUNK_CAPACITY` and had to split
+/// in two, with `T` the separator between the halves.
+enum Insertion<T> {
+    Fit(Arc<Node<T>>),
+    Split(Arc<Node<T>>, T, Arc<Node<T>>),
+}
+
+fn find_child_index<T, Q>(separators: &[T], value: &Q) -> usize
+where
+    T: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    match separators.binary_search_by(|separator| separator.borrow().cmp(value)) {
+        Ok(index) => index + 1,
+        Err(index) => index,
+    }
+}
+
+fn insert<T>(node: &Node<T>, value: T) -> (Insertion<T>, bool)
+where
+    T: Ord + Clone,
+{
+    match node {
+        Node::Leaf(items) => match items.binary_search_by(|item| item.cmp(&value)) {
+            // An equal element is already present: keep it, as with
+            // `super::Set::insert`, rather than the new `value`.
+            Ok(_) => (Insertion::Fit(Arc::new(Node::Leaf(items.clone()))), false),
+            Err(index) => {
+                let mut items = items.clone();
+                items.insert(index, value);
+                if items.len() <= CHUNK_CAPACITY {
+                    (Insertion::Fit(Arc::new(Node::Leaf(items))), true)
+                } else {
+                    let right = items.split_off(items.len() / 2);
+                    let separator = right[0].clone();
+                    let left = Arc::new(Node::Leaf(items));
+                    let right = Arc::new(Node::Leaf(right));
+                    (Insertion::Split(left, separator, right), true)
+                }
+            }
+        },
+        Node::Branch {
+            separators,
+            children,
+        } => {
+            let index = find_child_index(separators, &value);
+            let (result, inserted) = insert(&children[index], value);
+            let insertion = match result {
+                Insertion::Fit(child) => {
+                    let mut children = children.clone();
+                    children[index] = child;
+                    Insertion::Fit(Arc::new(Node::Branch {
+                        separators: separators.clone(),
+                        children,
+                    }))
+                }
+                Insertion::Split(left, separator, right) => {
+                    let mut children = children.clone();
+                    children[index] = left;
+                    children.insert(index + 1, right);
+                    let mut separators = separators.clone();
+                    separators.insert(index, separator);
+                    if children.len() <= CHUNK_CAPACITY {
+                        Insertion::Fit(Arc::new(Node::Branch {
+                            separators,
+                            children,
+                        }))
+                    } else {
+                        let mid = separators.len() / 2;
+                        let up = separators[mid].clone();
+                        let right_children = children.split_off(mid + 1);
+                        let right_separators = separators.split_off(mid + 1);
+                        // `separators[mid]` (the new `up`) is promoted to the
+                        // parent rather than kept on either side: popping it
+                        // off `separators` leaves exactly `mid` separators
+                        // for the `mid + 1` children kept in `left`, and
+                        // `right_separators` already holds exactly the
+                        // `right_children.len() - 1` entries split off above.
+                        separators.pop();
+                        let left = Arc::new(Node::Branch {
+                            separators,
+                            children,
+                        });
+                        let right = Arc::new(Node::Branch {
+                            separators: right_separators,
+                            children: right_children,
+                        });
+                        Insertion::Split(left, up, right)
+                    }
+                }
+            };
+            (insertion, inserted)
+        }
+    }
+}
+
+fn remove<T, Q>(node: &Node<T>, value: &Q) -> Arc<Node<T>>
+where
+    T: Ord + Clone + Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    match node {
+        Node::Leaf(items) => {
+            let mut items = items.clone();
+            if let Ok(index) = items.binary_search_by(|item| item.borrow().cmp(value)) {
+                items.remove(index);
+            }
+            Arc::new(Node::Leaf(items))
+        }
+        Node::Branch {
+            separators,
+            children,
+        } => {
+            let index = find_child_index(separators, value);
+            let mut children = children.clone();
+            children[index] = remove(&children[index], value);
+            // Underflowing branches/leaves are left as-is rather than
+            // merged with a sibling: `ImSet` is built for cheap forking
+            // under heavy insert/remove churn, not for minimizing node
+            // count, and merging on removal would mean cloning a sibling
+            // chunk on every delete instead of just the touched path.
+            Arc::new(Node::Branch {
+                separators: separators.clone(),
+                children,
+            })
+        }
+    }
+}
+
+fn contains<T, Q>(node: &Node<T>, value: &Q) -> bool
+where
+    T: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    match node {
+        Node::Leaf(items) => items
+            .binary_search_by(|item| item.borrow().cmp(value))
+            .is_ok(),
+        Node::Branch {
+            separators,
+            children,
+        } => contains(&children[find_child_index(separators, value)], value),
+    }
+}
+
+/// A persistent, immutable set with structural sharing.
+///
+/// Backed by a balanced, `Arc`-chunked B-tree: leaves hold small sorted
+/// arrays of elements, and `insert`/`remove` clone only the chunks on the
+/// path from the root to the touched leaf, sharing every other chunk with
+/// the set they were derived from. See the [module documentation](self) for
+/// when to reach for this over [`super::Set`].
+#[derive(Debug)]
+pub struct ImSet<T> {
+    root: Arc<Node<T>>,
+    len: usize,
+}
+
+impl<T> Clone for ImSet<T> {
+    /// `O(1)`: bumps the root `Arc`'s reference count without touching any
+    /// of the tree's chunks.
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<T> Default for ImSet<T> {
+    fn default() -> Self {
+        Self {
+            root: Node::empty_leaf(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> ImSet<T> {
+    /// Returns the number of elements in the [`ImSet`].
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the [`ImSet`] contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an iterator that yields the items in the [`ImSet`], in
+    /// ascending order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        let mut iter = Iter { stack: Vec::new() };
+        iter.descend_leftmost(&self.root);
+        iter
+    }
+}
+
+impl<T> ImSet<T>
+where
+    T: Ord,
+{
+    /// Returns true if the [`ImSet`] contains an element equal to the `value`.
+    pub fn contains<Q: ?Sized>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord,
+    {
+        contains(&self.root, value)
+    }
+}
+
+impl<T> ImSet<T>
+where
+    T: Ord + Clone,
+{
+    /// Returns a new [`ImSet`] with `value` added, sharing every chunk of
+    /// `self` that isn't on the path to where `value` belongs.
+    ///
+    /// If the set already contains an element equal to `value`, the
+    /// existing element is kept and the returned set is unchanged, as with
+    /// [`super::Set::insert`].
+    #[must_use = "ImSet is immutable; this returns a new set rather than mutating in place"]
+    pub fn insert(&self, value: T) -> Self {
+        let (result, inserted) = insert(&self.root, value);
+        let root = match result {
+            Insertion::Fit(root) => root,
+            Insertion::Split(left, separator, right) => Arc::new(Node::Branch {
+                separators: alloc::vec![separator],
+                children: alloc::vec![left, right],
+            }),
+        };
+        Self {
+            root,
+            len: self.len + usize::from(inserted),
+        }
+    }
+
+    /// Returns a new [`ImSet`] with any element equal to `value` removed,
+    /// sharing every chunk of `self` that isn't on the path to it.
+    #[must_use = "ImSet is immutable; this returns a new set rather than mutating in place"]
+    pub fn remove<Q: ?Sized>(&self, value: &Q) -> Self
+    where
+        T: Borrow<Q>,
+        Q: Ord,
+    {
+        if !self.contains(value) {
+            return self.clone();
+        }
+        Self {
+            root: remove(&self.root, value),
+            len: self.len - 1,
+        }
+    }
+
+    /// Returns an iterator over the elements of `self` that are not in `other`.
+    pub fn difference<'a>(&'a self, other: &'a ImSet<T>) -> Difference<'a, T> {
+        Difference {
+            iter: self.iter(),
+            other,
+        }
+    }
+
+    /// Returns an iterator over the elements in `self` or `other`, without duplicates.
+    pub fn union<'a>(&'a self, other: &'a ImSet<T>) -> Union<'a, T> {
+        Union(self.iter().chain(other.difference(self)))
+    }
+
+    /// Returns an iterator over the elements common to both `self` and `other`.
+    ///
+    /// Iterates the smaller of the two sets, testing membership in the larger one.
+    pub fn intersection<'a>(&'a self, other: &'a ImSet<T>) -> Intersection<'a, T> {
+        let (iter, other) = if self.len() <= other.len() {
+            (self.iter(), other)
+        } else {
+            (other.iter(), self)
+        };
+        Intersection { iter, other }
+    }
+
+    /// Returns an iterator over the elements in `self` or `other`, but not in both.
+    pub fn symmetric_difference<'a>(&'a self, other: &'a ImSet<T>) -> SymmetricDifference<'a, T> {
+        SymmetricDifference(self.difference(other).chain(other.difference(self)))
+    }
+}
+
+impl<T> FromIterator<T> for ImSet<T>
+where
+    T: Ord + Clone,
+{
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut set = Self::default();
+        for value in iter {
+            set = set.insert(value);
+        }
+        set
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ImSet<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+enum Frame<'a, T> {
+    Leaf(core::slice::Iter<'a, T>),
+    Branch {
+        children: &'a [Arc<Node<T>>],
+        next: usize,
+    },
+}
+
+/// An iterator over the items of an [`ImSet`], in ascending order.
+///
+/// This struct is created by [`ImSet::iter`].
+pub struct Iter<'a, T> {
+    stack: Vec<Frame<'a, T>>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    fn descend_leftmost(&mut self, mut node: &'a Node<T>) {
+        loop {
+            match node {
+                Node::Leaf(items) => {
+                    self.stack.push(Frame::Leaf(items.iter()));
+                    return;
+                }
+                Node::Branch { children, .. } => {
+                    self.stack.push(Frame::Branch { children, next: 1 });
+                    node = &children[0];
+                }
+            }
+        }
+    }
+}
+
+impl<'a, T: 'a> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.last_mut()? {
+                Frame::Leaf(items) => match items.next() {
+                    Some(item) => return Some(item),
+                    None => {
+                        self.stack.pop();
+                    }
+                },
+                Frame::Branch { children, next } => {
+                    if *next < children.len() {
+                        let child = &*children[*next];
+                        *next += 1;
+                        self.descend_leftmost(child);
+                    } else {
+                        self.stack.pop();
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+/// An iterator over the elements of an [`ImSet`] that are not in another.
+///
+/// This struct is created by [`ImSet::difference`].
+pub struct Difference<'a, T> {
+    iter: Iter<'a, T>,
+    other: &'a ImSet<T>,
+}
+
+impl<'a, T> Iterator for Difference<'a, T>
+where
+    T: Ord,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let item = self.iter.next()?;
+            if !self.other.contains(item) {
+                return Some(item);
+            }
+        }
+    }
+}
+
+impl<'a, T> FusedIterator for Difference<'a, T> where T: Ord {}
+
+/// An iterator over the elements common to two [`ImSet`]s.
+///
+/// This struct is created by [`ImSet::intersection`].
+pub struct Intersection<'a, T> {
+    iter: Iter<'a, T>,
+    other: &'a ImSet<T>,
+}
+
+impl<'a, T> Iterator for Intersection<'a, T>
+where
+    T: Ord,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let item = self.iter.next()?;
+            if self.other.contains(item) {
+                return Some(item);
+            }
+        }
+    }
+}
+
+impl<'a, T> FusedIterator for Intersection<'a, T> where T: Ord {}
+
+/// An iterator over the elements of two [`ImSet`]s, without duplicates.
+///
+/// This struct is created by [`ImSet::union`].
+pub struct Union<'a, T>(core::iter::Chain<Iter<'a, T>, Difference<'a, T>>);
+
+impl<'a, T> Iterator for Union<'a, T>
+where
+    T: Ord,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+impl<'a, T> FusedIterator for Union<'a, T> where T: Ord {}
+
+/// An iterator over the elements in one [`ImSet`] or the other, but not both.
+///
+/// This struct is created by [`ImSet::symmetric_difference`].
+pub struct SymmetricDifference<'a, T>(core::iter::Chain<Difference<'a, T>, Difference<'a, T>>);
+
+impl<'a, T> Iterator for SymmetricDifference<'a, T>
+where
+    T: Ord,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+impl<'a, T> FusedIterator for SymmetricDifference<'a, T> where T: Ord {}
+
+impl<T> BitOr<&ImSet<T>> for &ImSet<T>
+where
+    T: Ord + Clone,
+{
+    type Output = ImSet<T>;
+
+    /// Returns the union of `self` and `rhs` as a new [`ImSet`].
+    fn bitor(self, rhs: &ImSet<T>) -> ImSet<T> {
+        self.union(rhs).cloned().collect()
+    }
+}
+
+impl<T> BitAnd<&ImSet<T>> for &ImSet<T>
+where
+    T: Ord + Clone,
+{
+    type Output = ImSet<T>;
+
+    /// Returns the intersection of `self` and `rhs` as a new [`ImSet`].
+    fn bitand(self, rhs: &ImSet<T>) -> ImSet<T> {
+        self.intersection(rhs).cloned().collect()
+    }
+}
+
+impl<T> BitXor<&ImSet<T>> for &ImSet<T>
+where
+    T: Ord + Clone,
+{
+    type Output = ImSet<T>;
+
+    /// Returns the symmetric difference of `self` and `rhs` as a new [`ImSet`].
+    fn bitxor(self, rhs: &ImSet<T>) -> ImSet<T> {
+        self.symmetric_difference(rhs).cloned().collect()
+    }
+}
+
+impl<T> Sub<&ImSet<T>> for &ImSet<T>
+where
+    T: Ord + Clone,
+{
+    type Output = ImSet<T>;
+
+    /// Returns the elements of `self` that are not in `rhs` as a new [`ImSet`].
+    fn sub(self, rhs: &ImSet<T>) -> ImSet<T> {
+        self.difference(rhs).cloned().collect()
+    }
+}
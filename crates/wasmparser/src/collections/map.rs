@@ -3,9 +3,30 @@
 use core::borrow::Borrow;
 use core::hash::Hash;
 use core::iter::FusedIterator;
-use core::ops::Index;
+use core::ops::{Index, RangeBounds};
 
-#[cfg(not(feature = "no-hash-maps"))]
+// `preserve-order` takes priority over `no-hash-maps` when both are enabled,
+// the same way toml-rs's own `preserve_order` feature overrides its default
+// map backend: callers who explicitly asked for insertion order presumably
+// want it regardless of whatever else is turned on.
+#[cfg(feature = "preserve-order")]
+mod detail {
+    use crate::collections::hash;
+    use indexmap::{map as index_map, IndexMap};
+
+    pub type MapImpl<K, V> = IndexMap<K, V, hash::RandomState>;
+    pub type EntryImpl<'a, K, V> = index_map::Entry<'a, K, V>;
+    pub type OccupiedEntryImpl<'a, K, V> = index_map::OccupiedEntry<'a, K, V>;
+    pub type VacantEntryImpl<'a, K, V> = index_map::VacantEntry<'a, K, V>;
+    pub type IterImpl<'a, K, V> = index_map::Iter<'a, K, V>;
+    pub type IterMutImpl<'a, K, V> = index_map::IterMut<'a, K, V>;
+    pub type IntoIterImpl<K, V> = index_map::IntoIter<K, V>;
+    pub type KeysImpl<'a, K, V> = index_map::Keys<'a, K, V>;
+    pub type ValuesImpl<'a, K, V> = index_map::Values<'a, K, V>;
+    pub type ValuesMutImpl<'a, K, V> = index_map::ValuesMut<'a, K, V>;
+}
+
+#[cfg(all(not(feature = "preserve-order"), not(feature = "no-hash-maps")))]
 mod detail {
     use crate::collections::hash;
     use hashbrown::{hash_map, HashMap};
@@ -22,7 +43,7 @@ mod detail {
     pub type ValuesMutImpl<'a, K, V> = hash_map::ValuesMut<'a, K, V>;
 }
 
-#[cfg(feature = "no-hash-maps")]
+#[cfg(all(not(feature = "preserve-order"), feature = "no-hash-maps"))]
 mod detail {
     use alloc::collections::{btree_map, BTreeMap};
 
@@ -36,14 +57,21 @@ mod detail {
     pub type KeysImpl<'a, K, V> = btree_map::Keys<'a, K, V>;
     pub type ValuesImpl<'a, K, V> = btree_map::Values<'a, K, V>;
     pub type ValuesMutImpl<'a, K, V> = btree_map::ValuesMut<'a, K, V>;
+    // Only this backend is actually sorted by key (the hash backend has no
+    // key order, and `preserve-order`'s `IndexMap` preserves insertion
+    // order, not a sorted one), so only it gets `Range`/`RangeMut` impls.
+    pub type RangeImpl<'a, K, V> = btree_map::Range<'a, K, V>;
+    pub type RangeMutImpl<'a, K, V> = btree_map::RangeMut<'a, K, V>;
 }
 
 /// A default key-value mapping.
 ///
-/// Provides an API compatible with both [`HashMap`] and [`BTreeMap`].
+/// Provides an API compatible with [`HashMap`], [`BTreeMap`] and, when the
+/// `preserve-order` feature is enabled, [`IndexMap`].
 ///
 /// [`HashMap`]: hashbrown::HashMap
 /// [`BTreeMap`]: alloc::collections::BTreeMap
+/// [`IndexMap`]: indexmap::IndexMap
 #[derive(Debug, Clone)]
 pub struct Map<K, V> {
     inner: detail::MapImpl<K, V>,
@@ -115,12 +143,30 @@ where
 {
     /// Reserves capacity for at least `additional` more elements to be inserted in the [`Map`].
     pub fn reserve(&mut self, additional: usize) {
-        #[cfg(not(feature = "no-hash-maps"))]
+        #[cfg(any(feature = "preserve-order", not(feature = "no-hash-maps")))]
         self.inner.reserve(additional);
-        #[cfg(feature = "no-hash-maps")]
+        #[cfg(all(not(feature = "preserve-order"), feature = "no-hash-maps"))]
         let _ = additional;
     }
 
+    /// Tries to reserve capacity for at least `additional` more elements to
+    /// be inserted in the [`Map`], returning an error rather than aborting
+    /// if the allocator reports a failure.
+    ///
+    /// The `BTreeMap` backend (`no-hash-maps` without `preserve-order`) never
+    /// pre-allocates, so this is always `Ok(())` there.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), super::TryReserveError> {
+        #[cfg(any(feature = "preserve-order", not(feature = "no-hash-maps")))]
+        {
+            self.inner.try_reserve(additional).map_err(Into::into)
+        }
+        #[cfg(all(not(feature = "preserve-order"), feature = "no-hash-maps"))]
+        {
+            let _ = additional;
+            Ok(())
+        }
+    }
+
     /// Returns true if `key` is contains in the [`Map`].
     pub fn contains_key<Q: ?Sized>(&self, key: &Q) -> bool
     where
@@ -177,6 +223,108 @@ where
     }
 }
 
+/// Positional access, only meaningful for the array-backed `IndexMap`
+/// storage the `preserve-order` feature selects: the hash-map and btree-map
+/// backends have no stable notion of a slot index, so these methods simply
+/// don't exist for them rather than emulating one at `O(n)` or worse.
+#[cfg(feature = "preserve-order")]
+impl<K, V> Map<K, V> {
+    /// Returns the key-value pair at `index`, if any.
+    pub fn get_index(&self, index: usize) -> Option<(&K, &V)> {
+        self.inner.get_index(index)
+    }
+
+    /// Returns a mutable reference to the value at `index`, together with
+    /// its key, if any.
+    pub fn get_index_mut(&mut self, index: usize) -> Option<(&K, &mut V)> {
+        self.inner.get_index_mut(index)
+    }
+
+    /// Moves the key-value pair at `from` to `to`, shifting every entry in
+    /// between to make room, preserving the relative order of every other
+    /// entry at `O(|from - to|)` cost.
+    pub fn move_index(&mut self, from: usize, to: usize) {
+        self.inner.move_index(from, to)
+    }
+}
+
+#[cfg(feature = "preserve-order")]
+impl<K, V> Map<K, V>
+where
+    K: Hash + Eq + Ord,
+{
+    /// Removes the key from the map by swapping it with the last entry,
+    /// returning its value if it was present.
+    ///
+    /// This is `O(1)`, but does not preserve the relative order of the
+    /// remaining entries; use [`Map::shift_remove`] when order matters.
+    pub fn swap_remove<Q: ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + Ord,
+    {
+        self.inner.swap_remove(key)
+    }
+
+    /// Removes the key from the map, shifting every later entry to fill the
+    /// gap, returning its value if it was present.
+    ///
+    /// This preserves the relative order of the remaining entries, at `O(n)`
+    /// cost; use [`Map::swap_remove`] when order doesn't matter.
+    pub fn shift_remove<Q: ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + Ord,
+    {
+        self.inner.shift_remove(key)
+    }
+}
+
+/// Range queries and first/last access, only meaningful for a backend
+/// that's actually sorted by key: the hash backend has no key order, and
+/// the `preserve-order` `IndexMap` backend preserves insertion order rather
+/// than a sorted one, so these are only offered for the plain `BTreeMap`
+/// backend (`no-hash-maps` without `preserve-order`).
+#[cfg(all(not(feature = "preserve-order"), feature = "no-hash-maps"))]
+impl<K, V> Map<K, V>
+where
+    K: Ord,
+{
+    /// Returns an iterator over the entries of the [`Map`] whose keys fall in `range`, in key order.
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        Range {
+            inner: self.inner.range(range),
+        }
+    }
+
+    /// Returns a mutable iterator over the entries of the [`Map`] whose keys fall in `range`, in key order.
+    pub fn range_mut<Q, R>(&mut self, range: R) -> RangeMut<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        RangeMut {
+            inner: self.inner.range_mut(range),
+        }
+    }
+
+    /// Returns the first key-value pair in the [`Map`], by key order.
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.inner.first_key_value()
+    }
+
+    /// Returns the last key-value pair in the [`Map`], by key order.
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        self.inner.last_key_value()
+    }
+}
+
 impl<K, Q, V> Index<&Q> for Map<K, V>
 where
     K: Borrow<Q> + Hash + Eq + Ord,
@@ -220,6 +368,72 @@ where
             Self::Vacant(ref entry) => entry.key(),
         }
     }
+
+    /// Ensures a value is in the entry by inserting `default` if empty, and
+    /// returns a mutable reference to the value in the entry.
+    pub fn or_insert(self, default: V) -> &'a mut V
+    where
+        K: Hash,
+    {
+        self.or_insert_with(|| default)
+    }
+
+    /// Ensures a value is in the entry by inserting the result of `default`
+    /// if empty, and returns a mutable reference to the value in the entry.
+    pub fn or_insert_with<F>(self, default: F) -> &'a mut V
+    where
+        K: Hash,
+        F: FnOnce() -> V,
+    {
+        match self {
+            Self::Occupied(entry) => entry.into_mut(),
+            Self::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Like [`Entry::or_insert_with`], but `default` is passed a reference
+    /// to the entry's key, for the common case where building the default
+    /// value needs it.
+    pub fn or_insert_with_key<F>(self, default: F) -> &'a mut V
+    where
+        K: Hash,
+        F: FnOnce(&K) -> V,
+    {
+        match self {
+            Self::Occupied(entry) => entry.into_mut(),
+            Self::Vacant(entry) => {
+                let value = default(entry.key());
+                entry.insert(value)
+            }
+        }
+    }
+
+    /// Provides in-place mutable access to an occupied entry's value before
+    /// any potential inserts into the [`Map`]; a no-op for a vacant entry.
+    pub fn and_modify<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        match self {
+            Self::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Self::Occupied(entry)
+            }
+            Self::Vacant(entry) => Self::Vacant(entry),
+        }
+    }
+}
+
+impl<'a, K, V> Entry<'a, K, V>
+where
+    K: Ord + Hash,
+    V: Default,
+{
+    /// Ensures a value is in the entry by inserting `V::default()` if
+    /// empty, and returns a mutable reference to the value in the entry.
+    pub fn or_default(self) -> &'a mut V {
+        self.or_insert_with(V::default)
+    }
 }
 
 /// A view into an occupied entry in a [`Map`].
@@ -315,6 +529,73 @@ impl<'a, K, V> IntoIterator for &'a Map<K, V> {
     }
 }
 
+#[cfg(feature = "serde")]
+impl<K, V> serde::Serialize for Map<K, V>
+where
+    K: serde::Serialize,
+    V: serde::Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+
+        // Backend-agnostic: iterating `self.iter()` and serializing entries
+        // one at a time works the same whether that iteration order is a
+        // hash backend's arbitrary order or an ordered backend's key/
+        // insertion order, and for the latter it's what makes the
+        // serialized output reproducible.
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self.iter() {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, K, V> serde::Deserialize<'de> for Map<K, V>
+where
+    K: serde::Deserialize<'de> + Hash + Eq + Ord,
+    V: serde::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_map(MapVisitor(core::marker::PhantomData))
+    }
+}
+
+#[cfg(feature = "serde")]
+struct MapVisitor<K, V>(core::marker::PhantomData<fn() -> Map<K, V>>);
+
+#[cfg(feature = "serde")]
+impl<'de, K, V> serde::de::Visitor<'de> for MapVisitor<K, V>
+where
+    K: serde::Deserialize<'de> + Hash + Eq + Ord,
+    V: serde::Deserialize<'de>,
+{
+    type Value = Map<K, V>;
+
+    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        formatter.write_str("a map")
+    }
+
+    fn visit_map<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        let mut map = Map::default();
+        map.reserve(access.size_hint().unwrap_or(0));
+        while let Some((key, value)) = access.next_entry()? {
+            map.insert(key, value);
+        }
+        Ok(map)
+    }
+}
+
 /// An iterator over the items of a [`Map`].
 #[derive(Debug, Clone)]
 pub struct Iter<'a, K, V> {
@@ -369,6 +650,62 @@ impl<'a, K, V> ExactSizeIterator for IterMut<'a, K, V> {
 impl<'a, K, V> FusedIterator for IterMut<'a, K, V> where detail::IterMutImpl<'a, K, V>: FusedIterator
 {}
 
+/// An iterator over a sub-range of the entries of a [`Map`], in key order.
+///
+/// See [`Map::range`]. Only available for the plain `BTreeMap` backend.
+#[cfg(all(not(feature = "preserve-order"), feature = "no-hash-maps"))]
+#[derive(Debug, Clone)]
+pub struct Range<'a, K, V> {
+    inner: detail::RangeImpl<'a, K, V>,
+}
+
+#[cfg(all(not(feature = "preserve-order"), feature = "no-hash-maps"))]
+impl<'a, K, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+#[cfg(all(not(feature = "preserve-order"), feature = "no-hash-maps"))]
+impl<'a, K, V> DoubleEndedIterator for Range<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+#[cfg(all(not(feature = "preserve-order"), feature = "no-hash-maps"))]
+impl<'a, K, V> FusedIterator for Range<'a, K, V> {}
+
+/// A mutable iterator over a sub-range of the entries of a [`Map`], in key order.
+///
+/// See [`Map::range_mut`]. Only available for the plain `BTreeMap` backend.
+#[cfg(all(not(feature = "preserve-order"), feature = "no-hash-maps"))]
+#[derive(Debug)]
+pub struct RangeMut<'a, K, V> {
+    inner: detail::RangeMutImpl<'a, K, V>,
+}
+
+#[cfg(all(not(feature = "preserve-order"), feature = "no-hash-maps"))]
+impl<'a, K, V> Iterator for RangeMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+#[cfg(all(not(feature = "preserve-order"), feature = "no-hash-maps"))]
+impl<'a, K, V> DoubleEndedIterator for RangeMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+#[cfg(all(not(feature = "preserve-order"), feature = "no-hash-maps"))]
+impl<'a, K, V> FusedIterator for RangeMut<'a, K, V> {}
+
 impl<K, V> IntoIterator for Map<K, V> {
     type Item = (K, V);
     type IntoIter = IntoIter<K, V>;
@@ -0,0 +1,62 @@
+//! Default collection types used throughout `wasmparser`.
+
+pub mod hash;
+pub mod imset;
+pub mod map;
+pub mod set;
+
+pub use imset::ImSet;
+pub use map::Map;
+pub use set::Set;
+
+/// The error returned by [`Map::try_reserve`] and [`Set::try_reserve`] when
+/// the requested capacity cannot be allocated.
+///
+/// [`Map::try_reserve`]: map::Map::try_reserve
+/// [`Set::try_reserve`]: set::Set::try_reserve
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryReserveError {
+    /// The requested capacity exceeds `isize::MAX` bytes. Every failure of
+    /// the `preserve-order` backend, allocator errors included, is also
+    /// reported as this variant.
+    CapacityOverflow,
+    /// The allocator returned an error when allocating the requested `layout`.
+    AllocError {
+        /// The memory layout that allocation failed for.
+        layout: core::alloc::Layout,
+    },
+}
+
+impl core::fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::CapacityOverflow => f.write_str(
+                "memory allocation failed because the computed capacity exceeded the collection's maximum",
+            ),
+            Self::AllocError { layout } => {
+                write!(f, "memory allocation of {} bytes failed", layout.size())
+            }
+        }
+    }
+}
+
+impl core::error::Error for TryReserveError {}
+
+impl From<hashbrown::TryReserveError> for TryReserveError {
+    fn from(err: hashbrown::TryReserveError) -> Self {
+        match err {
+            hashbrown::TryReserveError::CapacityOverflow => Self::CapacityOverflow,
+            hashbrown::TryReserveError::AllocError { layout } => Self::AllocError { layout },
+        }
+    }
+}
+
+#[cfg(feature = "preserve-order")]
+impl From<indexmap::TryReserveError> for TryReserveError {
+    fn from(_err: indexmap::TryReserveError) -> Self {
+        // Unlike `hashbrown::TryReserveError`, `indexmap::TryReserveError`
+        // doesn't expose which case it is or the `Layout` involved, so there
+        // is nothing to preserve beyond the fact that allocation failed.
+        Self::CapacityOverflow
+    }
+}
@@ -2,27 +2,55 @@
 
 use core::borrow::Borrow;
 use core::hash::Hash;
-use core::iter::FusedIterator;
+use core::iter::{Chain, FusedIterator};
+use core::ops::{BitAnd, BitOr, BitXor, Sub};
 
-#[cfg(not(feature = "no-hash-maps"))]
+// See `map::detail`'s comment: `preserve-order` takes priority over
+// `no-hash-maps` when both are enabled.
+#[cfg(feature = "preserve-order")]
+mod detail {
+    use crate::collections::hash;
+
+    pub type SetImpl<T> = indexmap::IndexSet<T, hash::RandomState>;
+    pub type IterImpl<'a, T> = indexmap::set::Iter<'a, T>;
+    pub type IntoIterImpl<T> = indexmap::set::IntoIter<T>;
+    #[cfg(feature = "rayon")]
+    pub type ParIterImpl<'a, T> = indexmap::set::rayon::ParIter<'a, T, hash::RandomState>;
+    #[cfg(feature = "rayon")]
+    pub type IntoParIterImpl<T> = indexmap::set::rayon::IntoParIter<T, hash::RandomState>;
+}
+
+#[cfg(all(not(feature = "preserve-order"), not(feature = "no-hash-maps")))]
 mod detail {
     use crate::collections::hash;
 
     pub type SetImpl<T> = hashbrown::HashSet<T, hash::RandomState>;
     pub type IterImpl<'a, T> = hashbrown::hash_set::Iter<'a, T>;
     pub type IntoIterImpl<T> = hashbrown::hash_set::IntoIter<T>;
+    #[cfg(feature = "rayon")]
+    pub type ParIterImpl<'a, T> = hashbrown::hash_set::rayon::ParIter<'a, T, hash::RandomState>;
+    #[cfg(feature = "rayon")]
+    pub type IntoParIterImpl<T> = hashbrown::hash_set::rayon::IntoParIter<T, hash::RandomState>;
 }
 
-#[cfg(feature = "no-hash-maps")]
+#[cfg(all(not(feature = "preserve-order"), feature = "no-hash-maps"))]
 mod detail {
     pub type SetImpl<T> = alloc::collections::BTreeSet<T>;
     pub type IterImpl<'a, T> = alloc::collections::btree_set::Iter<'a, T>;
     pub type IntoIterImpl<T> = alloc::collections::btree_set::IntoIter<T>;
+    // `BTreeSet` has no native Rayon support, so its parallel iterators are
+    // bridged by collecting into a `Vec` first and handing that to Rayon's
+    // own `IntoParallelIterator` impl for vectors.
+    #[cfg(feature = "rayon")]
+    pub type ParIterImpl<'a, T> = rayon::vec::IntoIter<&'a T>;
+    #[cfg(feature = "rayon")]
+    pub type IntoParIterImpl<T> = rayon::vec::IntoIter<T>;
 }
 
 /// A default set of values.
 ///
-/// Provides a unified API between a hash-set and a btree-set.
+/// Provides a unified API between a hash-set, a btree-set and, when the
+/// `preserve-order` feature is enabled, [`indexmap::IndexSet`].
 #[derive(Debug, Clone)]
 pub struct Set<T> {
     /// The underlying hash-set or btree-set data structure used.
@@ -67,12 +95,30 @@ where
 {
     /// Reserves capacity for at least `additional` more elements to be inserted in the [`Set`].
     pub fn reserve(&mut self, additional: usize) {
-        #[cfg(not(feature = "no-hash-maps"))]
+        #[cfg(any(feature = "preserve-order", not(feature = "no-hash-maps")))]
         self.inner.reserve(additional);
-        #[cfg(feature = "no-hash-maps")]
+        #[cfg(all(not(feature = "preserve-order"), feature = "no-hash-maps"))]
         let _ = additional;
     }
 
+    /// Tries to reserve capacity for at least `additional` more elements to
+    /// be inserted in the [`Set`], returning an error rather than aborting
+    /// if the allocator reports a failure.
+    ///
+    /// The `BTreeSet` backend (`no-hash-maps` without `preserve-order`) never
+    /// pre-allocates, so this is always `Ok(())` there.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), super::TryReserveError> {
+        #[cfg(any(feature = "preserve-order", not(feature = "no-hash-maps")))]
+        {
+            self.inner.try_reserve(additional).map_err(Into::into)
+        }
+        #[cfg(all(not(feature = "preserve-order"), feature = "no-hash-maps"))]
+        {
+            let _ = additional;
+            Ok(())
+        }
+    }
+
     /// Returns true if the [`Set`] contains an element equal to the `value`.
     pub fn contains<Q: ?Sized>(&self, value: &Q) -> bool
     where
@@ -111,6 +157,36 @@ where
     {
         self.inner.remove(value)
     }
+
+    /// Returns an iterator over the elements of `self` that are not in `other`.
+    pub fn difference<'a>(&'a self, other: &'a Set<T>) -> Difference<'a, T> {
+        Difference {
+            iter: self.iter(),
+            other,
+        }
+    }
+
+    /// Returns an iterator over the elements in `self` or `other`, but not in both.
+    pub fn symmetric_difference<'a>(&'a self, other: &'a Set<T>) -> SymmetricDifference<'a, T> {
+        SymmetricDifference(self.difference(other).chain(other.difference(self)))
+    }
+
+    /// Returns an iterator over the elements common to both `self` and `other`.
+    ///
+    /// Iterates the smaller of the two sets, testing membership in the larger one.
+    pub fn intersection<'a>(&'a self, other: &'a Set<T>) -> Intersection<'a, T> {
+        let (iter, other) = if self.len() <= other.len() {
+            (self.iter(), other)
+        } else {
+            (other.iter(), self)
+        };
+        Intersection { iter, other }
+    }
+
+    /// Returns an iterator over the elements of `self` and `other`, without duplicates.
+    pub fn union<'a>(&'a self, other: &'a Set<T>) -> Union<'a, T> {
+        Union(self.iter().chain(other.difference(self)))
+    }
 }
 
 impl<T> FromIterator<T> for Set<T>
@@ -199,3 +275,336 @@ impl<T> ExactSizeIterator for IntoIter<T> {
 }
 
 impl<T> FusedIterator for IntoIter<T> {}
+
+/// An iterator over the elements of a [`Set`] that are not in another.
+///
+/// This struct is created by [`Set::difference`].
+#[derive(Debug, Clone)]
+pub struct Difference<'a, T> {
+    iter: Iter<'a, T>,
+    other: &'a Set<T>,
+}
+
+impl<'a, T> Iterator for Difference<'a, T>
+where
+    T: Hash + Eq + Ord,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let item = self.iter.next()?;
+            if !self.other.contains(item) {
+                return Some(item);
+            }
+        }
+    }
+}
+
+impl<'a, T> FusedIterator for Difference<'a, T> where T: Hash + Eq + Ord {}
+
+/// An iterator over the elements common to two [`Set`]s.
+///
+/// This struct is created by [`Set::intersection`].
+#[derive(Debug, Clone)]
+pub struct Intersection<'a, T> {
+    iter: Iter<'a, T>,
+    other: &'a Set<T>,
+}
+
+impl<'a, T> Iterator for Intersection<'a, T>
+where
+    T: Hash + Eq + Ord,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let item = self.iter.next()?;
+            if self.other.contains(item) {
+                return Some(item);
+            }
+        }
+    }
+}
+
+impl<'a, T> FusedIterator for Intersection<'a, T> where T: Hash + Eq + Ord {}
+
+/// An iterator over the elements of two [`Set`]s, without duplicates.
+///
+/// This struct is created by [`Set::union`].
+#[derive(Debug, Clone)]
+pub struct Union<'a, T>(Chain<Iter<'a, T>, Difference<'a, T>>);
+
+impl<'a, T> Iterator for Union<'a, T>
+where
+    T: Hash + Eq + Ord,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+impl<'a, T> FusedIterator for Union<'a, T> where T: Hash + Eq + Ord {}
+
+/// An iterator over the elements in one [`Set`] or the other, but not both.
+///
+/// This struct is created by [`Set::symmetric_difference`].
+#[derive(Debug, Clone)]
+pub struct SymmetricDifference<'a, T>(Chain<Difference<'a, T>, Difference<'a, T>>);
+
+impl<'a, T> Iterator for SymmetricDifference<'a, T>
+where
+    T: Hash + Eq + Ord,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+impl<'a, T> FusedIterator for SymmetricDifference<'a, T> where T: Hash + Eq + Ord {}
+
+impl<T> BitOr<&Set<T>> for &Set<T>
+where
+    T: Hash + Eq + Ord + Clone,
+{
+    type Output = Set<T>;
+
+    /// Returns the union of `self` and `rhs` as a new [`Set`].
+    fn bitor(self, rhs: &Set<T>) -> Set<T> {
+        self.union(rhs).cloned().collect()
+    }
+}
+
+impl<T> BitAnd<&Set<T>> for &Set<T>
+where
+    T: Hash + Eq + Ord + Clone,
+{
+    type Output = Set<T>;
+
+    /// Returns the intersection of `self` and `rhs` as a new [`Set`].
+    fn bitand(self, rhs: &Set<T>) -> Set<T> {
+        self.intersection(rhs).cloned().collect()
+    }
+}
+
+impl<T> BitXor<&Set<T>> for &Set<T>
+where
+    T: Hash + Eq + Ord + Clone,
+{
+    type Output = Set<T>;
+
+    /// Returns the symmetric difference of `self` and `rhs` as a new [`Set`].
+    fn bitxor(self, rhs: &Set<T>) -> Set<T> {
+        self.symmetric_difference(rhs).cloned().collect()
+    }
+}
+
+impl<T> Sub<&Set<T>> for &Set<T>
+where
+    T: Hash + Eq + Ord + Clone,
+{
+    type Output = Set<T>;
+
+    /// Returns the elements of `self` that are not in `rhs` as a new [`Set`].
+    fn sub(self, rhs: &Set<T>) -> Set<T> {
+        self.difference(rhs).cloned().collect()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for Set<T>
+where
+    T: serde::Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for Set<T>
+where
+    T: serde::Deserialize<'de> + Hash + Eq + Ord,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(SetVisitor(core::marker::PhantomData))
+    }
+}
+
+#[cfg(feature = "serde")]
+struct SetVisitor<T>(core::marker::PhantomData<fn() -> Set<T>>);
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::de::Visitor<'de> for SetVisitor<T>
+where
+    T: serde::Deserialize<'de> + Hash + Eq + Ord,
+{
+    type Value = Set<T>;
+
+    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        formatter.write_str("a sequence")
+    }
+
+    fn visit_seq<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        let mut set = Set::default();
+        set.reserve(access.size_hint().unwrap_or(0));
+        while let Some(value) = access.next_element()? {
+            set.insert(value);
+        }
+        Ok(set)
+    }
+}
+
+#[cfg(feature = "rayon")]
+use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelExtend, ParallelIterator};
+
+#[cfg(feature = "rayon")]
+impl<T> Set<T>
+where
+    T: Hash + Eq + Ord + Sync,
+{
+    /// Returns a Rayon parallel iterator that yields the items in the [`Set`].
+    ///
+    /// Requires the `rayon` feature.
+    pub fn par_iter(&self) -> ParIter<'_, T> {
+        #[cfg(any(feature = "preserve-order", not(feature = "no-hash-maps")))]
+        let inner = self.inner.par_iter();
+        #[cfg(all(not(feature = "preserve-order"), feature = "no-hash-maps"))]
+        let inner = self
+            .inner
+            .iter()
+            .collect::<alloc::vec::Vec<_>>()
+            .into_par_iter();
+        ParIter { inner }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T> IntoParallelIterator for Set<T>
+where
+    T: Hash + Eq + Ord + Send,
+{
+    type Item = T;
+    type Iter = IntoParIter<T>;
+
+    /// Returns a Rayon parallel iterator that yields the owned items of the [`Set`].
+    fn into_par_iter(self) -> Self::Iter {
+        #[cfg(any(feature = "preserve-order", not(feature = "no-hash-maps")))]
+        let inner = self.inner.into_par_iter();
+        #[cfg(all(not(feature = "preserve-order"), feature = "no-hash-maps"))]
+        let inner = self
+            .inner
+            .into_iter()
+            .collect::<alloc::vec::Vec<_>>()
+            .into_par_iter();
+        IntoParIter { inner }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T> ParallelExtend<T> for Set<T>
+where
+    T: Hash + Eq + Ord + Send,
+{
+    fn par_extend<I>(&mut self, par_iter: I)
+    where
+        I: IntoParallelIterator<Item = T>,
+    {
+        #[cfg(any(feature = "preserve-order", not(feature = "no-hash-maps")))]
+        {
+            self.inner.par_extend(par_iter);
+        }
+        // `BTreeSet` has no concurrent insert to delegate to, so the
+        // parallelism here is limited to producing `par_iter`'s items; the
+        // inserts themselves happen on the calling thread.
+        #[cfg(all(not(feature = "preserve-order"), feature = "no-hash-maps"))]
+        {
+            self.extend(par_iter.into_par_iter().collect::<alloc::vec::Vec<_>>());
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T> FromParallelIterator<T> for Set<T>
+where
+    T: Hash + Eq + Ord + Send,
+{
+    fn from_par_iter<I>(par_iter: I) -> Self
+    where
+        I: IntoParallelIterator<Item = T>,
+    {
+        let mut set = Self::default();
+        set.par_extend(par_iter);
+        set
+    }
+}
+
+/// A Rayon parallel iterator over the items of a [`Set`].
+///
+/// This struct is created by [`Set::par_iter`]. Requires the `rayon` feature.
+#[cfg(feature = "rayon")]
+pub struct ParIter<'a, T> {
+    inner: detail::ParIterImpl<'a, T>,
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T> ParallelIterator for ParIter<'a, T>
+where
+    T: Sync,
+    detail::ParIterImpl<'a, T>: ParallelIterator<Item = &'a T>,
+{
+    type Item = &'a T;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: rayon::iter::plumbing::UnindexedConsumer<Self::Item>,
+    {
+        self.inner.drive_unindexed(consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        self.inner.opt_len()
+    }
+}
+
+/// A Rayon parallel iterator over the owned items of a [`Set`].
+///
+/// This struct is created by [`Set::into_par_iter`]. Requires the `rayon` feature.
+#[cfg(feature = "rayon")]
+pub struct IntoParIter<T> {
+    inner: detail::IntoParIterImpl<T>,
+}
+
+#[cfg(feature = "rayon")]
+impl<T> ParallelIterator for IntoParIter<T>
+where
+    T: Send,
+    detail::IntoParIterImpl<T>: ParallelIterator<Item = T>,
+{
+    type Item = T;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: rayon::iter::plumbing::UnindexedConsumer<Self::Item>,
+    {
+        self.inner.drive_unindexed(consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        self.inner.opt_len()
+    }
+}
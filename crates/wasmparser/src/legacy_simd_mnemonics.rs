@@ -0,0 +1,48 @@
+//! A table of pre-finalization SIMD mnemonics and the canonical name each one
+//! was renamed to when the `simd` proposal was finalized.
+//!
+//! This crate only parses the binary format, so the opt-in compatibility mode
+//! for recognizing these deprecated spellings belongs in the text (`.wat`)
+//! parser; that parser is not part of this checkout, so this module only
+//! provides the lookup table, keyed off the canonical names already carried
+//! by [`for_each_simd_operator!`], for that parser to consume.
+//!
+//! [`for_each_simd_operator!`]: crate::for_each_simd_operator
+
+/// Pairs of `(deprecated_mnemonic, canonical_mnemonic)` for every SIMD
+/// mnemonic renamed when the proposal was finalized.
+///
+/// `canonical_mnemonic` always matches the `visit_*` name of a variant
+/// enumerated by [`for_each_simd_operator!`], minus its `visit_` prefix, with
+/// underscores in place of dots.
+///
+/// [`for_each_simd_operator!`]: crate::for_each_simd_operator
+pub const LEGACY_SIMD_MNEMONICS: &[(&str, &str)] = &[
+    ("i8x16.add_saturate_s", "i8x16.add_sat_s"),
+    ("i8x16.add_saturate_u", "i8x16.add_sat_u"),
+    ("i8x16.sub_saturate_s", "i8x16.sub_sat_s"),
+    ("i8x16.sub_saturate_u", "i8x16.sub_sat_u"),
+    ("i16x8.add_saturate_s", "i16x8.add_sat_s"),
+    ("i16x8.add_saturate_u", "i16x8.add_sat_u"),
+    ("i16x8.sub_saturate_s", "i16x8.sub_sat_s"),
+    ("i16x8.sub_saturate_u", "i16x8.sub_sat_u"),
+    ("i16x8.widen_low_i8x16_s", "i16x8.extend_low_i8x16_s"),
+    ("i16x8.widen_high_i8x16_s", "i16x8.extend_high_i8x16_s"),
+    ("i16x8.widen_low_i8x16_u", "i16x8.extend_low_i8x16_u"),
+    ("i16x8.widen_high_i8x16_u", "i16x8.extend_high_i8x16_u"),
+    ("i32x4.widen_low_i16x8_s", "i32x4.extend_low_i16x8_s"),
+    ("i32x4.widen_high_i16x8_s", "i32x4.extend_high_i16x8_s"),
+    ("i32x4.widen_low_i16x8_u", "i32x4.extend_low_i16x8_u"),
+    ("i32x4.widen_high_i16x8_u", "i32x4.extend_high_i16x8_u"),
+    ("v128.swizzle", "i8x16.swizzle"),
+    ("v128.shuffle", "i8x16.shuffle"),
+];
+
+/// Returns the canonical, finalized-spec mnemonic for `mnemonic`, or `None`
+/// if `mnemonic` is not a recognized pre-finalization alias.
+pub fn canonical_simd_mnemonic(mnemonic: &str) -> Option<&'static str> {
+    LEGACY_SIMD_MNEMONICS
+        .iter()
+        .find(|(legacy, _)| *legacy == mnemonic)
+        .map(|(_, canonical)| *canonical)
+}
@@ -0,0 +1,278 @@
+//! A deterministic-lowering pass for `@relaxed_simd` operators.
+//!
+//! Relaxed SIMD operators are implementation-defined, which is at odds with
+//! content-addressed caching, differential testing, and any target that
+//! wants a single canonical semantics. [`lower_relaxed_simd`] rewrites a
+//! relaxed operator into a fixed, deterministic sequence built only from
+//! `@simd` operators.
+
+use crate::simd_operator::SimdOperator;
+use alloc::vec::Vec;
+
+/// An operand referenced by a [`RelaxedSimdStep`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelaxedOperand {
+    /// The relaxed operator's own `n`th stack input (0-indexed, in the order
+    /// the original operator's [`signature`](SimdOperator::signature) pops
+    /// them).
+    Input(u8),
+    /// The result of an earlier step in the same expansion, by index.
+    Temp(usize),
+    /// A constant `i32`.
+    ConstI32(i32),
+}
+
+/// One step of a [`lower_relaxed_simd`] expansion: apply `op` to `operands`,
+/// binding its result to a fresh temporary that later steps (and, for the
+/// final step, the site being rewritten) can reference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelaxedSimdStep {
+    /// The non-relaxed operator this step applies.
+    pub op: SimdOperator,
+    /// The operands this step consumes, in order.
+    pub operands: Vec<RelaxedOperand>,
+}
+
+fn step(op: SimdOperator, operands: Vec<RelaxedOperand>) -> RelaxedSimdStep {
+    RelaxedSimdStep { op, operands }
+}
+
+/// An [`I8x16Shuffle`](SimdOperator::I8x16Shuffle) lane table that moves a
+/// vector's even-indexed bytes (0, 2, 4, ..., 14) down into the low half of
+/// the result, duplicated into the high half too since only one half is
+/// ever read back out.
+const EVEN_BYTE_SHUFFLE: [u8; 16] = [0, 2, 4, 6, 8, 10, 12, 14, 0, 2, 4, 6, 8, 10, 12, 14];
+
+/// As [`EVEN_BYTE_SHUFFLE`], but for the odd-indexed bytes (1, 3, 5, ..., 15).
+const ODD_BYTE_SHUFFLE: [u8; 16] = [1, 3, 5, 7, 9, 11, 13, 15, 1, 3, 5, 7, 9, 11, 13, 15];
+
+/// Lowers a `@relaxed_simd` operator into a fixed, deterministic sequence of
+/// `@simd` operators with identical stack-type behavior, or returns `None` if
+/// `op` is not a relaxed operator (there is nothing to lower).
+///
+/// A caller materializing this expansion into an instruction sequence
+/// replaces the original instruction with the final step and inserts the
+/// earlier steps immediately before it, spilling each [`RelaxedOperand::Temp`]
+/// to a scratch local if its consuming step is not adjacent.
+pub fn lower_relaxed_simd(op: &SimdOperator) -> Option<Vec<RelaxedSimdStep>> {
+    use RelaxedOperand::*;
+    use SimdOperator::*;
+
+    Some(match op {
+        F32x4RelaxedMadd => alloc::vec![
+            step(F32x4Mul, alloc::vec![Input(0), Input(1)]),
+            step(F32x4Add, alloc::vec![Temp(0), Input(2)]),
+        ],
+        F32x4RelaxedNmadd => alloc::vec![
+            step(F32x4Mul, alloc::vec![Input(0), Input(1)]),
+            step(F32x4Sub, alloc::vec![Input(2), Temp(0)]),
+        ],
+        F64x2RelaxedMadd => alloc::vec![
+            step(F64x2Mul, alloc::vec![Input(0), Input(1)]),
+            step(F64x2Add, alloc::vec![Temp(0), Input(2)]),
+        ],
+        F64x2RelaxedNmadd => alloc::vec![
+            step(F64x2Mul, alloc::vec![Input(0), Input(1)]),
+            step(F64x2Sub, alloc::vec![Input(2), Temp(0)]),
+        ],
+        F32x4RelaxedMin => alloc::vec![step(F32x4Min, alloc::vec![Input(0), Input(1)])],
+        F32x4RelaxedMax => alloc::vec![step(F32x4Max, alloc::vec![Input(0), Input(1)])],
+        F64x2RelaxedMin => alloc::vec![step(F64x2Min, alloc::vec![Input(0), Input(1)])],
+        F64x2RelaxedMax => alloc::vec![step(F64x2Max, alloc::vec![Input(0), Input(1)])],
+        I32x4RelaxedTruncF32x4S => alloc::vec![step(I32x4TruncSatF32x4S, alloc::vec![Input(0)])],
+        I32x4RelaxedTruncF32x4U => alloc::vec![step(I32x4TruncSatF32x4U, alloc::vec![Input(0)])],
+        I32x4RelaxedTruncF64x2SZero => {
+            alloc::vec![step(I32x4TruncSatF64x2SZero, alloc::vec![Input(0)])]
+        }
+        I32x4RelaxedTruncF64x2UZero => {
+            alloc::vec![step(I32x4TruncSatF64x2UZero, alloc::vec![Input(0)])]
+        }
+        I16x8RelaxedQ15mulrS => {
+            alloc::vec![step(I16x8Q15MulrSatS, alloc::vec![Input(0), Input(1)])]
+        }
+        // `i8x16.swizzle` already zeroes out-of-range indices per the base
+        // `simd` spec, so the relaxed and non-relaxed variants coincide.
+        I8x16RelaxedSwizzle => alloc::vec![step(I8x16Swizzle, alloc::vec![Input(0), Input(1)])],
+        // Arithmetic-shift the mask's top bit across each lane to turn a
+        // 0/-1-or-garbage mask into a proper all-0s/all-1s [`V128Bitselect`]
+        // mask, by the lane-width-minus-one amount for each width.
+        I8x16RelaxedLaneselect => alloc::vec![
+            step(I8x16ShrS, alloc::vec![Input(2), ConstI32(7)]),
+            step(V128Bitselect, alloc::vec![Input(0), Input(1), Temp(0)]),
+        ],
+        I16x8RelaxedLaneselect => alloc::vec![
+            step(I16x8ShrS, alloc::vec![Input(2), ConstI32(15)]),
+            step(V128Bitselect, alloc::vec![Input(0), Input(1), Temp(0)]),
+        ],
+        I32x4RelaxedLaneselect => alloc::vec![
+            step(I32x4ShrS, alloc::vec![Input(2), ConstI32(31)]),
+            step(V128Bitselect, alloc::vec![Input(0), Input(1), Temp(0)]),
+        ],
+        I64x2RelaxedLaneselect => alloc::vec![
+            step(I64x2ShrS, alloc::vec![Input(2), ConstI32(63)]),
+            step(V128Bitselect, alloc::vec![Input(0), Input(1), Temp(0)]),
+        ],
+        // `i16x8.relaxed_dot_i8x16_i7x16_s` sums *adjacent* byte-pair
+        // products into each output lane (`a[2i]*b[2i] + a[2i+1]*b[2i+1]`,
+        // the `PMADDUBSW` pairing), not the low/high-half pairing that
+        // `ExtMulLow`/`ExtMulHigh` give directly. Shuffle each operand's
+        // even- and odd-indexed bytes down into its low half first, so that
+        // `ExtMulLow` of the shuffled operands multiplies the adjacent
+        // pairs we actually want, then add the two halves together.
+        I16x8RelaxedDotI8x16I7x16S => alloc::vec![
+            step(
+                I8x16Shuffle {
+                    lanes: EVEN_BYTE_SHUFFLE,
+                },
+                alloc::vec![Input(0), Input(0)],
+            ),
+            step(
+                I8x16Shuffle {
+                    lanes: EVEN_BYTE_SHUFFLE,
+                },
+                alloc::vec![Input(1), Input(1)],
+            ),
+            step(
+                I8x16Shuffle {
+                    lanes: ODD_BYTE_SHUFFLE,
+                },
+                alloc::vec![Input(0), Input(0)],
+            ),
+            step(
+                I8x16Shuffle {
+                    lanes: ODD_BYTE_SHUFFLE,
+                },
+                alloc::vec![Input(1), Input(1)],
+            ),
+            step(I16x8ExtMulLowI8x16S, alloc::vec![Temp(0), Temp(1)]),
+            step(I16x8ExtMulLowI8x16S, alloc::vec![Temp(2), Temp(3)]),
+            step(I16x8Add, alloc::vec![Temp(4), Temp(5)]),
+        ],
+        I32x4RelaxedDotI8x16I7x16AddS => alloc::vec![
+            step(
+                I8x16Shuffle {
+                    lanes: EVEN_BYTE_SHUFFLE,
+                },
+                alloc::vec![Input(0), Input(0)],
+            ),
+            step(
+                I8x16Shuffle {
+                    lanes: EVEN_BYTE_SHUFFLE,
+                },
+                alloc::vec![Input(1), Input(1)],
+            ),
+            step(
+                I8x16Shuffle {
+                    lanes: ODD_BYTE_SHUFFLE,
+                },
+                alloc::vec![Input(0), Input(0)],
+            ),
+            step(
+                I8x16Shuffle {
+                    lanes: ODD_BYTE_SHUFFLE,
+                },
+                alloc::vec![Input(1), Input(1)],
+            ),
+            step(I16x8ExtMulLowI8x16S, alloc::vec![Temp(0), Temp(1)]),
+            step(I16x8ExtMulLowI8x16S, alloc::vec![Temp(2), Temp(3)]),
+            step(I16x8Add, alloc::vec![Temp(4), Temp(5)]),
+            step(I32x4ExtAddPairwiseI16x8S, alloc::vec![Temp(6)]),
+            step(I32x4Add, alloc::vec![Temp(7), Input(2)]),
+        ],
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simd_const_eval::{eval_simd_operator, SimdConst};
+
+    /// Runs a [`lower_relaxed_simd`] expansion against concrete `inputs`,
+    /// feeding each step's operands (resolved from `inputs` and earlier
+    /// steps' results) through [`eval_simd_operator`], and returns the final
+    /// step's result.
+    fn run_lowering(op: SimdOperator, inputs: &[SimdConst]) -> SimdConst {
+        let steps = lower_relaxed_simd(&op).expect("op is a relaxed operator");
+        let mut temps: Vec<SimdConst> = Vec::new();
+        for step in &steps {
+            let operands: Vec<SimdConst> = step
+                .operands
+                .iter()
+                .map(|operand| match operand {
+                    RelaxedOperand::Input(i) => inputs[*i as usize],
+                    RelaxedOperand::Temp(i) => temps[*i],
+                    RelaxedOperand::ConstI32(v) => SimdConst::I32(*v),
+                })
+                .collect();
+            let result = eval_simd_operator(&step.op, &operands)
+                .expect("lowering only uses non-relaxed operators");
+            temps.push(result);
+        }
+        *temps
+            .last()
+            .expect("a lowering always has at least one step")
+    }
+
+    fn v128_from_i8_lanes(lanes: [i8; 16]) -> crate::V128 {
+        crate::V128::from(lanes.map(|l| l as u8))
+    }
+
+    fn i16_lanes_of(v: crate::V128) -> [i16; 8] {
+        let b = v.bytes();
+        core::array::from_fn(|i| i16::from_le_bytes([b[2 * i], b[2 * i + 1]]))
+    }
+
+    /// Direct interpretation of `i16x8.relaxed_dot_i8x16_i7x16_s`: each
+    /// output lane sums the products of *adjacent* input byte pairs
+    /// (`a[2i]*b[2i] + a[2i+1]*b[2i+1]`), the same pairing
+    /// `simd_const_eval`'s `I32x4DotI16x8S` uses one lane width up.
+    fn expected_relaxed_dot(a: [i8; 16], b: [i8; 16]) -> [i16; 8] {
+        core::array::from_fn(|i| {
+            (i16::from(a[2 * i]) * i16::from(b[2 * i]))
+                .wrapping_add(i16::from(a[2 * i + 1]) * i16::from(b[2 * i + 1]))
+        })
+    }
+
+    #[test]
+    fn relaxed_dot_i8x16_i7x16_s_pairs_adjacent_bytes() {
+        let cases: [[i8; 16]; 3] = [
+            [0; 16],
+            core::array::from_fn(|i| (i as i8) - 8),
+            [
+                1,
+                -1,
+                2,
+                -2,
+                3,
+                -3,
+                4,
+                -4,
+                5,
+                -5,
+                6,
+                -6,
+                7,
+                -7,
+                i8::MAX,
+                i8::MIN,
+            ],
+        ];
+
+        for &a in &cases {
+            for &b in &cases {
+                let result = run_lowering(
+                    SimdOperator::I16x8RelaxedDotI8x16I7x16S,
+                    &[
+                        SimdConst::V128(v128_from_i8_lanes(a)),
+                        SimdConst::V128(v128_from_i8_lanes(b)),
+                    ],
+                );
+                let SimdConst::V128(result) = result else {
+                    panic!("expected a v128 result");
+                };
+                assert_eq!(i16_lanes_of(result), expected_relaxed_dot(a, b));
+            }
+        }
+    }
+}
@@ -0,0 +1,1105 @@
+//! A constant-folding evaluator for pure (non-memory, non-relaxed) `@simd`
+//! operators.
+//!
+//! [`eval_simd_operator`] evaluates a [`SimdOperator`] against concrete
+//! inputs (typically the payload of `V128Const`/scalar `Const` operators),
+//! returning the value it would leave on the stack. It returns `None` for
+//! operators whose result isn't uniquely defined without runtime state:
+//! memory/atomic operators (there is no memory to read) and `@relaxed_simd`
+//! operators (see [`lower_relaxed_simd`](crate::relaxed_simd_lowering::lower_relaxed_simd)
+//! to turn those into a deterministic `@simd` sequence first).
+//!
+//! The saturating float-to-int truncations (`I32x4TruncSatF32x4*` and the
+//! `Zero`-suffixed `F64x2` variants) lean on Rust's `as` cast between floats
+//! and integers, which has saturated at the target type's bounds and mapped
+//! NaN to zero since Rust 1.45 -- exactly the `trunc_sat` semantics the
+//! `simd` proposal specifies.
+
+use crate::simd_operator::SimdOperator;
+
+/// A constant value produced or consumed while folding [`SimdOperator`]s.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SimdConst {
+    #[allow(missing_docs)]
+    V128(crate::V128),
+    #[allow(missing_docs)]
+    I32(i32),
+    #[allow(missing_docs)]
+    I64(i64),
+    #[allow(missing_docs)]
+    F32(f32),
+    #[allow(missing_docs)]
+    F64(f64),
+}
+
+impl SimdConst {
+    fn as_v128(self) -> Option<crate::V128> {
+        match self {
+            SimdConst::V128(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    fn as_i32(self) -> Option<i32> {
+        match self {
+            SimdConst::I32(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    fn as_i64(self) -> Option<i64> {
+        match self {
+            SimdConst::I64(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    fn as_f32(self) -> Option<f32> {
+        match self {
+            SimdConst::F32(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    fn as_f64(self) -> Option<f64> {
+        match self {
+            SimdConst::F64(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+fn v128_in(inputs: &[SimdConst], i: usize) -> Option<crate::V128> {
+    inputs.get(i).copied()?.as_v128()
+}
+
+fn i32_in(inputs: &[SimdConst], i: usize) -> Option<i32> {
+    inputs.get(i).copied()?.as_i32()
+}
+
+fn i64_in(inputs: &[SimdConst], i: usize) -> Option<i64> {
+    inputs.get(i).copied()?.as_i64()
+}
+
+fn f32_in(inputs: &[SimdConst], i: usize) -> Option<f32> {
+    inputs.get(i).copied()?.as_f32()
+}
+
+fn f64_in(inputs: &[SimdConst], i: usize) -> Option<f64> {
+    inputs.get(i).copied()?.as_f64()
+}
+
+macro_rules! define_lane_view {
+    ($get:ident, $set:ident, $ty:ty, $count:literal, $width:literal) => {
+        fn $get(v: &crate::V128) -> [$ty; $count] {
+            let b = v.bytes();
+            core::array::from_fn(|i| {
+                let mut buf = [0u8; $width];
+                buf.copy_from_slice(&b[i * $width..(i + 1) * $width]);
+                <$ty>::from_le_bytes(buf)
+            })
+        }
+
+        fn $set(lanes: [$ty; $count]) -> crate::V128 {
+            let mut out = [0u8; 16];
+            for (i, l) in lanes.into_iter().enumerate() {
+                out[i * $width..(i + 1) * $width].copy_from_slice(&l.to_le_bytes());
+            }
+            crate::V128::from(out)
+        }
+    };
+}
+
+define_lane_view!(lanes_i8, from_lanes_i8, i8, 16, 1);
+define_lane_view!(lanes_u8, from_lanes_u8, u8, 16, 1);
+define_lane_view!(lanes_i16, from_lanes_i16, i16, 8, 2);
+define_lane_view!(lanes_u16, from_lanes_u16, u16, 8, 2);
+define_lane_view!(lanes_i32, from_lanes_i32, i32, 4, 4);
+define_lane_view!(lanes_u32, from_lanes_u32, u32, 4, 4);
+define_lane_view!(lanes_i64, from_lanes_i64, i64, 2, 8);
+define_lane_view!(lanes_u64, from_lanes_u64, u64, 2, 8);
+define_lane_view!(lanes_f32, from_lanes_f32, f32, 4, 4);
+define_lane_view!(lanes_f64, from_lanes_f64, f64, 2, 8);
+
+fn map<T: Copy, const N: usize>(a: [T; N], f: impl Fn(T) -> T) -> [T; N] {
+    a.map(f)
+}
+
+fn map2<T: Copy, const N: usize>(a: [T; N], b: [T; N], f: impl Fn(T, T) -> T) -> [T; N] {
+    core::array::from_fn(|i| f(a[i], b[i]))
+}
+
+fn map3<T: Copy, const N: usize>(a: [T; N], b: [T; N], c: [T; N], f: impl Fn(T, T, T) -> T) -> [T; N] {
+    core::array::from_fn(|i| f(a[i], b[i], c[i]))
+}
+
+fn convert<A: Copy, B, const N: usize>(a: [A; N], f: impl Fn(A) -> B) -> [B; N] {
+    core::array::from_fn(|i| f(a[i]))
+}
+
+macro_rules! define_wasm_minmax {
+    ($min:ident, $max:ident, $ty:ty) => {
+        /// `fmin`/`fmax` per the `simd` proposal: NaN-propagating (unlike
+        /// `$ty::min`/`$ty::max`, which return the non-NaN operand), and
+        /// `-0.0 < +0.0` for the purpose of picking a signed zero.
+        fn $min(a: $ty, b: $ty) -> $ty {
+            if a.is_nan() || b.is_nan() {
+                <$ty>::NAN
+            } else if a == 0.0 && b == 0.0 {
+                if a.is_sign_negative() || b.is_sign_negative() {
+                    -0.0
+                } else {
+                    0.0
+                }
+            } else {
+                a.min(b)
+            }
+        }
+
+        fn $max(a: $ty, b: $ty) -> $ty {
+            if a.is_nan() || b.is_nan() {
+                <$ty>::NAN
+            } else if a == 0.0 && b == 0.0 {
+                if a.is_sign_positive() || b.is_sign_positive() {
+                    0.0
+                } else {
+                    -0.0
+                }
+            } else {
+                a.max(b)
+            }
+        }
+    };
+}
+
+define_wasm_minmax!(wasm_fmin32, wasm_fmax32, f32);
+define_wasm_minmax!(wasm_fmin64, wasm_fmax64, f64);
+
+macro_rules! unop {
+    ($inputs:ident, $get:ident, $set:ident, $f:expr) => {
+        Some(SimdConst::V128($set(map($get(&v128_in($inputs, 0)?), $f))))
+    };
+}
+
+macro_rules! binop {
+    ($inputs:ident, $get:ident, $set:ident, $f:expr) => {
+        Some(SimdConst::V128($set(map2(
+            $get(&v128_in($inputs, 0)?),
+            $get(&v128_in($inputs, 1)?),
+            $f,
+        ))))
+    };
+}
+
+macro_rules! ternop {
+    ($inputs:ident, $get:ident, $set:ident, $f:expr) => {
+        Some(SimdConst::V128($set(map3(
+            $get(&v128_in($inputs, 0)?),
+            $get(&v128_in($inputs, 1)?),
+            $get(&v128_in($inputs, 2)?),
+            $f,
+        ))))
+    };
+}
+
+macro_rules! shiftop {
+    ($inputs:ident, $get:ident, $set:ident, $bits:literal, $f:expr) => {{
+        let a = $get(&v128_in($inputs, 0)?);
+        let amt = (i32_in($inputs, 1)? as u32) % $bits;
+        Some(SimdConst::V128($set(map(a, |x| $f(x, amt)))))
+    }};
+}
+
+macro_rules! convop {
+    ($inputs:ident, $get:ident, $set:ident, $f:expr) => {
+        Some(SimdConst::V128($set(convert($get(&v128_in($inputs, 0)?), $f))))
+    };
+}
+
+/// Evaluates `op` against `inputs`, returning the resulting [`SimdConst`], or
+/// `None` if `op` is a memory/atomic operator (no memory to read), a
+/// `@relaxed_simd` operator (implementation-defined), or simply not yet
+/// covered by this evaluator.
+pub fn eval_simd_operator(op: &SimdOperator, inputs: &[SimdConst]) -> Option<SimdConst> {
+    use SimdOperator::*;
+
+    match op {
+        // -- splat --
+        I8x16Splat => Some(SimdConst::V128(from_lanes_i8([i32_in(inputs, 0)? as i8; 16]))),
+        I16x8Splat => Some(SimdConst::V128(from_lanes_i16([i32_in(inputs, 0)? as i16; 8]))),
+        I32x4Splat => Some(SimdConst::V128(from_lanes_i32([i32_in(inputs, 0)?; 4]))),
+        I64x2Splat => Some(SimdConst::V128(from_lanes_i64([i64_in(inputs, 0)?; 2]))),
+        F32x4Splat => Some(SimdConst::V128(from_lanes_f32([f32_in(inputs, 0)?; 4]))),
+        F64x2Splat => Some(SimdConst::V128(from_lanes_f64([f64_in(inputs, 0)?; 2]))),
+
+        // -- bitwise --
+        V128Not => unop!(inputs, lanes_u8, from_lanes_u8, |a: u8| !a),
+        V128And => binop!(inputs, lanes_u8, from_lanes_u8, |a: u8, b: u8| a & b),
+        V128AndNot => binop!(inputs, lanes_u8, from_lanes_u8, |a: u8, b: u8| a & !b),
+        V128Or => binop!(inputs, lanes_u8, from_lanes_u8, |a: u8, b: u8| a | b),
+        V128Xor => binop!(inputs, lanes_u8, from_lanes_u8, |a: u8, b: u8| a ^ b),
+        V128Bitselect => ternop!(inputs, lanes_u8, from_lanes_u8, |a: u8, b: u8, c: u8| (a
+            & c)
+            | (b & !c)),
+
+        // -- i8x16 arithmetic --
+        I8x16Abs => unop!(inputs, lanes_i8, from_lanes_i8, |a: i8| a.wrapping_abs()),
+        I8x16Neg => unop!(inputs, lanes_i8, from_lanes_i8, |a: i8| a.wrapping_neg()),
+        I8x16Popcnt => unop!(inputs, lanes_u8, from_lanes_u8, |a: u8| a.count_ones() as u8),
+        I8x16Add => binop!(inputs, lanes_i8, from_lanes_i8, |a: i8, b: i8| a
+            .wrapping_add(b)),
+        I8x16AddSatS => binop!(inputs, lanes_i8, from_lanes_i8, |a: i8, b: i8| a
+            .saturating_add(b)),
+        I8x16AddSatU => binop!(inputs, lanes_u8, from_lanes_u8, |a: u8, b: u8| a
+            .saturating_add(b)),
+        I8x16Sub => binop!(inputs, lanes_i8, from_lanes_i8, |a: i8, b: i8| a
+            .wrapping_sub(b)),
+        I8x16SubSatS => binop!(inputs, lanes_i8, from_lanes_i8, |a: i8, b: i8| a
+            .saturating_sub(b)),
+        I8x16SubSatU => binop!(inputs, lanes_u8, from_lanes_u8, |a: u8, b: u8| a
+            .saturating_sub(b)),
+        I8x16MinS => binop!(inputs, lanes_i8, from_lanes_i8, |a: i8, b: i8| a.min(b)),
+        I8x16MinU => binop!(inputs, lanes_u8, from_lanes_u8, |a: u8, b: u8| a.min(b)),
+        I8x16MaxS => binop!(inputs, lanes_i8, from_lanes_i8, |a: i8, b: i8| a.max(b)),
+        I8x16MaxU => binop!(inputs, lanes_u8, from_lanes_u8, |a: u8, b: u8| a.max(b)),
+        I8x16AvgrU => binop!(inputs, lanes_u8, from_lanes_u8, |a: u8, b: u8| (a as u16
+            + b as u16)
+            .div_ceil(2) as u8),
+        I8x16Shl => shiftop!(inputs, lanes_i8, from_lanes_i8, 8, |x: i8, n: u32| x
+            .wrapping_shl(n)),
+        I8x16ShrS => shiftop!(inputs, lanes_i8, from_lanes_i8, 8, |x: i8, n: u32| x
+            .wrapping_shr(n)),
+        I8x16ShrU => shiftop!(inputs, lanes_u8, from_lanes_u8, 8, |x: u8, n: u32| x
+            .wrapping_shr(n)),
+
+        // -- i16x8 arithmetic --
+        I16x8Abs => unop!(inputs, lanes_i16, from_lanes_i16, |a: i16| a.wrapping_abs()),
+        I16x8Neg => unop!(inputs, lanes_i16, from_lanes_i16, |a: i16| a.wrapping_neg()),
+        I16x8Add => binop!(inputs, lanes_i16, from_lanes_i16, |a: i16, b: i16| a
+            .wrapping_add(b)),
+        I16x8AddSatS => binop!(inputs, lanes_i16, from_lanes_i16, |a: i16, b: i16| a
+            .saturating_add(b)),
+        I16x8AddSatU => binop!(inputs, lanes_u16, from_lanes_u16, |a: u16, b: u16| a
+            .saturating_add(b)),
+        I16x8Sub => binop!(inputs, lanes_i16, from_lanes_i16, |a: i16, b: i16| a
+            .wrapping_sub(b)),
+        I16x8SubSatS => binop!(inputs, lanes_i16, from_lanes_i16, |a: i16, b: i16| a
+            .saturating_sub(b)),
+        I16x8SubSatU => binop!(inputs, lanes_u16, from_lanes_u16, |a: u16, b: u16| a
+            .saturating_sub(b)),
+        I16x8Mul => binop!(inputs, lanes_i16, from_lanes_i16, |a: i16, b: i16| a
+            .wrapping_mul(b)),
+        I16x8MinS => binop!(inputs, lanes_i16, from_lanes_i16, |a: i16, b: i16| a.min(b)),
+        I16x8MinU => binop!(inputs, lanes_u16, from_lanes_u16, |a: u16, b: u16| a.min(b)),
+        I16x8MaxS => binop!(inputs, lanes_i16, from_lanes_i16, |a: i16, b: i16| a.max(b)),
+        I16x8MaxU => binop!(inputs, lanes_u16, from_lanes_u16, |a: u16, b: u16| a.max(b)),
+        I16x8AvgrU => binop!(inputs, lanes_u16, from_lanes_u16, |a: u16, b: u16| (a as u32
+            + b as u32)
+            .div_ceil(2) as u16),
+        I16x8Q15MulrSatS => binop!(inputs, lanes_i16, from_lanes_i16, |a: i16, b: i16| {
+            let r = (i32::from(a) * i32::from(b) + 0x4000) >> 15;
+            r.clamp(i32::from(i16::MIN), i32::from(i16::MAX)) as i16
+        }),
+        I16x8Shl => shiftop!(inputs, lanes_i16, from_lanes_i16, 16, |x: i16, n: u32| x
+            .wrapping_shl(n)),
+        I16x8ShrS => shiftop!(inputs, lanes_i16, from_lanes_i16, 16, |x: i16, n: u32| x
+            .wrapping_shr(n)),
+        I16x8ShrU => shiftop!(inputs, lanes_u16, from_lanes_u16, 16, |x: u16, n: u32| x
+            .wrapping_shr(n)),
+
+        // -- i32x4 arithmetic --
+        I32x4Abs => unop!(inputs, lanes_i32, from_lanes_i32, |a: i32| a.wrapping_abs()),
+        I32x4Neg => unop!(inputs, lanes_i32, from_lanes_i32, |a: i32| a.wrapping_neg()),
+        I32x4Add => binop!(inputs, lanes_i32, from_lanes_i32, |a: i32, b: i32| a
+            .wrapping_add(b)),
+        I32x4Sub => binop!(inputs, lanes_i32, from_lanes_i32, |a: i32, b: i32| a
+            .wrapping_sub(b)),
+        I32x4Mul => binop!(inputs, lanes_i32, from_lanes_i32, |a: i32, b: i32| a
+            .wrapping_mul(b)),
+        I32x4MinS => binop!(inputs, lanes_i32, from_lanes_i32, |a: i32, b: i32| a.min(b)),
+        I32x4MinU => binop!(inputs, lanes_u32, from_lanes_u32, |a: u32, b: u32| a.min(b)),
+        I32x4MaxS => binop!(inputs, lanes_i32, from_lanes_i32, |a: i32, b: i32| a.max(b)),
+        I32x4MaxU => binop!(inputs, lanes_u32, from_lanes_u32, |a: u32, b: u32| a.max(b)),
+        I32x4DotI16x8S => {
+            let a = lanes_i16(&v128_in(inputs, 0)?);
+            let b = lanes_i16(&v128_in(inputs, 1)?);
+            Some(SimdConst::V128(from_lanes_i32(core::array::from_fn(|i| {
+                i32::from(a[2 * i]) * i32::from(b[2 * i])
+                    + i32::from(a[2 * i + 1]) * i32::from(b[2 * i + 1])
+            }))))
+        }
+        I32x4Shl => shiftop!(inputs, lanes_i32, from_lanes_i32, 32, |x: i32, n: u32| x
+            .wrapping_shl(n)),
+        I32x4ShrS => shiftop!(inputs, lanes_i32, from_lanes_i32, 32, |x: i32, n: u32| x
+            .wrapping_shr(n)),
+        I32x4ShrU => shiftop!(inputs, lanes_u32, from_lanes_u32, 32, |x: u32, n: u32| x
+            .wrapping_shr(n)),
+
+        // -- i64x2 arithmetic --
+        I64x2Abs => unop!(inputs, lanes_i64, from_lanes_i64, |a: i64| a.wrapping_abs()),
+        I64x2Neg => unop!(inputs, lanes_i64, from_lanes_i64, |a: i64| a.wrapping_neg()),
+        I64x2Add => binop!(inputs, lanes_i64, from_lanes_i64, |a: i64, b: i64| a
+            .wrapping_add(b)),
+        I64x2Sub => binop!(inputs, lanes_i64, from_lanes_i64, |a: i64, b: i64| a
+            .wrapping_sub(b)),
+        I64x2Mul => binop!(inputs, lanes_i64, from_lanes_i64, |a: i64, b: i64| a
+            .wrapping_mul(b)),
+        I64x2Shl => shiftop!(inputs, lanes_i64, from_lanes_i64, 64, |x: i64, n: u32| x
+            .wrapping_shl(n)),
+        I64x2ShrS => shiftop!(inputs, lanes_i64, from_lanes_i64, 64, |x: i64, n: u32| x
+            .wrapping_shr(n)),
+        I64x2ShrU => shiftop!(inputs, lanes_u64, from_lanes_u64, 64, |x: u64, n: u32| x
+            .wrapping_shr(n)),
+
+        // -- float arithmetic --
+        F32x4Ceil => unop!(inputs, lanes_f32, from_lanes_f32, |a: f32| a.ceil()),
+        F32x4Floor => unop!(inputs, lanes_f32, from_lanes_f32, |a: f32| a.floor()),
+        F32x4Trunc => unop!(inputs, lanes_f32, from_lanes_f32, |a: f32| a.trunc()),
+        F32x4Nearest => unop!(inputs, lanes_f32, from_lanes_f32, |a: f32| a.round_ties_even()),
+        F32x4Abs => unop!(inputs, lanes_f32, from_lanes_f32, |a: f32| a.abs()),
+        F32x4Neg => unop!(inputs, lanes_f32, from_lanes_f32, |a: f32| -a),
+        F32x4Sqrt => unop!(inputs, lanes_f32, from_lanes_f32, |a: f32| a.sqrt()),
+        F32x4Add => binop!(inputs, lanes_f32, from_lanes_f32, |a: f32, b: f32| a + b),
+        F32x4Sub => binop!(inputs, lanes_f32, from_lanes_f32, |a: f32, b: f32| a - b),
+        F32x4Mul => binop!(inputs, lanes_f32, from_lanes_f32, |a: f32, b: f32| a * b),
+        F32x4Div => binop!(inputs, lanes_f32, from_lanes_f32, |a: f32, b: f32| a / b),
+        F32x4Min => binop!(inputs, lanes_f32, from_lanes_f32, |a: f32, b: f32| wasm_fmin32(
+            a, b
+        )),
+        F32x4Max => binop!(inputs, lanes_f32, from_lanes_f32, |a: f32, b: f32| wasm_fmax32(
+            a, b
+        )),
+        F32x4PMin => binop!(inputs, lanes_f32, from_lanes_f32, |a: f32, b: f32| if b
+            < a
+        {
+            b
+        } else {
+            a
+        }),
+        F32x4PMax => binop!(inputs, lanes_f32, from_lanes_f32, |a: f32, b: f32| if a
+            < b
+        {
+            b
+        } else {
+            a
+        }),
+        F64x2Ceil => unop!(inputs, lanes_f64, from_lanes_f64, |a: f64| a.ceil()),
+        F64x2Floor => unop!(inputs, lanes_f64, from_lanes_f64, |a: f64| a.floor()),
+        F64x2Trunc => unop!(inputs, lanes_f64, from_lanes_f64, |a: f64| a.trunc()),
+        F64x2Nearest => unop!(inputs, lanes_f64, from_lanes_f64, |a: f64| a.round_ties_even()),
+        F64x2Abs => unop!(inputs, lanes_f64, from_lanes_f64, |a: f64| a.abs()),
+        F64x2Neg => unop!(inputs, lanes_f64, from_lanes_f64, |a: f64| -a),
+        F64x2Sqrt => unop!(inputs, lanes_f64, from_lanes_f64, |a: f64| a.sqrt()),
+        F64x2Add => binop!(inputs, lanes_f64, from_lanes_f64, |a: f64, b: f64| a + b),
+        F64x2Sub => binop!(inputs, lanes_f64, from_lanes_f64, |a: f64, b: f64| a - b),
+        F64x2Mul => binop!(inputs, lanes_f64, from_lanes_f64, |a: f64, b: f64| a * b),
+        F64x2Div => binop!(inputs, lanes_f64, from_lanes_f64, |a: f64, b: f64| a / b),
+        F64x2Min => binop!(inputs, lanes_f64, from_lanes_f64, |a: f64, b: f64| wasm_fmin64(
+            a, b
+        )),
+        F64x2Max => binop!(inputs, lanes_f64, from_lanes_f64, |a: f64, b: f64| wasm_fmax64(
+            a, b
+        )),
+        F64x2PMin => binop!(inputs, lanes_f64, from_lanes_f64, |a: f64, b: f64| if b
+            < a
+        {
+            b
+        } else {
+            a
+        }),
+        F64x2PMax => binop!(inputs, lanes_f64, from_lanes_f64, |a: f64, b: f64| if a
+            < b
+        {
+            b
+        } else {
+            a
+        }),
+
+        // -- integer comparisons (mask is all-1s/all-0s per lane) --
+        I8x16Eq => binop!(inputs, lanes_i8, from_lanes_i8, |a: i8, b: i8| if a == b {
+            -1
+        } else {
+            0
+        }),
+        I8x16Ne => binop!(inputs, lanes_i8, from_lanes_i8, |a: i8, b: i8| if a != b {
+            -1
+        } else {
+            0
+        }),
+        I8x16LtS => binop!(inputs, lanes_i8, from_lanes_i8, |a: i8, b: i8| if a < b {
+            -1
+        } else {
+            0
+        }),
+        I8x16LtU => binop!(inputs, lanes_u8, from_lanes_u8, |a: u8, b: u8| if a < b {
+            0xff
+        } else {
+            0
+        }),
+        I8x16GtS => binop!(inputs, lanes_i8, from_lanes_i8, |a: i8, b: i8| if a > b {
+            -1
+        } else {
+            0
+        }),
+        I8x16GtU => binop!(inputs, lanes_u8, from_lanes_u8, |a: u8, b: u8| if a > b {
+            0xff
+        } else {
+            0
+        }),
+        I8x16LeS => binop!(inputs, lanes_i8, from_lanes_i8, |a: i8, b: i8| if a <= b {
+            -1
+        } else {
+            0
+        }),
+        I8x16LeU => binop!(inputs, lanes_u8, from_lanes_u8, |a: u8, b: u8| if a <= b {
+            0xff
+        } else {
+            0
+        }),
+        I8x16GeS => binop!(inputs, lanes_i8, from_lanes_i8, |a: i8, b: i8| if a >= b {
+            -1
+        } else {
+            0
+        }),
+        I8x16GeU => binop!(inputs, lanes_u8, from_lanes_u8, |a: u8, b: u8| if a >= b {
+            0xff
+        } else {
+            0
+        }),
+
+        I16x8Eq => binop!(inputs, lanes_i16, from_lanes_i16, |a: i16, b: i16| if a
+            == b
+        {
+            -1
+        } else {
+            0
+        }),
+        I16x8Ne => binop!(inputs, lanes_i16, from_lanes_i16, |a: i16, b: i16| if a
+            != b
+        {
+            -1
+        } else {
+            0
+        }),
+        I16x8LtS => binop!(inputs, lanes_i16, from_lanes_i16, |a: i16, b: i16| if a
+            < b
+        {
+            -1
+        } else {
+            0
+        }),
+        I16x8LtU => binop!(inputs, lanes_u16, from_lanes_u16, |a: u16, b: u16| if a
+            < b
+        {
+            0xffff
+        } else {
+            0
+        }),
+        I16x8GtS => binop!(inputs, lanes_i16, from_lanes_i16, |a: i16, b: i16| if a
+            > b
+        {
+            -1
+        } else {
+            0
+        }),
+        I16x8GtU => binop!(inputs, lanes_u16, from_lanes_u16, |a: u16, b: u16| if a
+            > b
+        {
+            0xffff
+        } else {
+            0
+        }),
+        I16x8LeS => binop!(inputs, lanes_i16, from_lanes_i16, |a: i16, b: i16| if a
+            <= b
+        {
+            -1
+        } else {
+            0
+        }),
+        I16x8LeU => binop!(inputs, lanes_u16, from_lanes_u16, |a: u16, b: u16| if a
+            <= b
+        {
+            0xffff
+        } else {
+            0
+        }),
+        I16x8GeS => binop!(inputs, lanes_i16, from_lanes_i16, |a: i16, b: i16| if a
+            >= b
+        {
+            -1
+        } else {
+            0
+        }),
+        I16x8GeU => binop!(inputs, lanes_u16, from_lanes_u16, |a: u16, b: u16| if a
+            >= b
+        {
+            0xffff
+        } else {
+            0
+        }),
+
+        I32x4Eq => binop!(inputs, lanes_i32, from_lanes_i32, |a: i32, b: i32| if a
+            == b
+        {
+            -1
+        } else {
+            0
+        }),
+        I32x4Ne => binop!(inputs, lanes_i32, from_lanes_i32, |a: i32, b: i32| if a
+            != b
+        {
+            -1
+        } else {
+            0
+        }),
+        I32x4LtS => binop!(inputs, lanes_i32, from_lanes_i32, |a: i32, b: i32| if a
+            < b
+        {
+            -1
+        } else {
+            0
+        }),
+        I32x4LtU => binop!(inputs, lanes_u32, from_lanes_u32, |a: u32, b: u32| if a
+            < b
+        {
+            0xffff_ffff
+        } else {
+            0
+        }),
+        I32x4GtS => binop!(inputs, lanes_i32, from_lanes_i32, |a: i32, b: i32| if a
+            > b
+        {
+            -1
+        } else {
+            0
+        }),
+        I32x4GtU => binop!(inputs, lanes_u32, from_lanes_u32, |a: u32, b: u32| if a
+            > b
+        {
+            0xffff_ffff
+        } else {
+            0
+        }),
+        I32x4LeS => binop!(inputs, lanes_i32, from_lanes_i32, |a: i32, b: i32| if a
+            <= b
+        {
+            -1
+        } else {
+            0
+        }),
+        I32x4LeU => binop!(inputs, lanes_u32, from_lanes_u32, |a: u32, b: u32| if a
+            <= b
+        {
+            0xffff_ffff
+        } else {
+            0
+        }),
+        I32x4GeS => binop!(inputs, lanes_i32, from_lanes_i32, |a: i32, b: i32| if a
+            >= b
+        {
+            -1
+        } else {
+            0
+        }),
+        I32x4GeU => binop!(inputs, lanes_u32, from_lanes_u32, |a: u32, b: u32| if a
+            >= b
+        {
+            0xffff_ffff
+        } else {
+            0
+        }),
+
+        // I64x2 only has signed comparisons; the `simd` proposal never added
+        // unsigned `Lt`/`Gt`/`Le`/`Ge` variants for 64-bit lanes.
+        I64x2Eq => binop!(inputs, lanes_i64, from_lanes_i64, |a: i64, b: i64| if a
+            == b
+        {
+            -1
+        } else {
+            0
+        }),
+        I64x2Ne => binop!(inputs, lanes_i64, from_lanes_i64, |a: i64, b: i64| if a
+            != b
+        {
+            -1
+        } else {
+            0
+        }),
+        I64x2LtS => binop!(inputs, lanes_i64, from_lanes_i64, |a: i64, b: i64| if a
+            < b
+        {
+            -1
+        } else {
+            0
+        }),
+        I64x2GtS => binop!(inputs, lanes_i64, from_lanes_i64, |a: i64, b: i64| if a
+            > b
+        {
+            -1
+        } else {
+            0
+        }),
+        I64x2LeS => binop!(inputs, lanes_i64, from_lanes_i64, |a: i64, b: i64| if a
+            <= b
+        {
+            -1
+        } else {
+            0
+        }),
+        I64x2GeS => binop!(inputs, lanes_i64, from_lanes_i64, |a: i64, b: i64| if a
+            >= b
+        {
+            -1
+        } else {
+            0
+        }),
+
+        // -- splat / test / narrow / extend / extract / replace / shuffle / swizzle --
+        V128AnyTrue => {
+            let a = v128_in(inputs, 0)?.bytes();
+            Some(SimdConst::I32(i32::from(a.iter().any(|&b| b != 0))))
+        }
+        I8x16AllTrue => {
+            let a = lanes_i8(&v128_in(inputs, 0)?);
+            Some(SimdConst::I32(i32::from(a.iter().all(|&x| x != 0))))
+        }
+        I8x16Bitmask => {
+            let a = lanes_i8(&v128_in(inputs, 0)?);
+            Some(SimdConst::I32(
+                a.iter().enumerate().fold(
+                    0,
+                    |mask, (i, &x)| if x < 0 { mask | (1 << i) } else { mask },
+                ),
+            ))
+        }
+        I16x8AllTrue => {
+            let a = lanes_i16(&v128_in(inputs, 0)?);
+            Some(SimdConst::I32(i32::from(a.iter().all(|&x| x != 0))))
+        }
+        I16x8Bitmask => {
+            let a = lanes_i16(&v128_in(inputs, 0)?);
+            Some(SimdConst::I32(
+                a.iter().enumerate().fold(
+                    0,
+                    |mask, (i, &x)| if x < 0 { mask | (1 << i) } else { mask },
+                ),
+            ))
+        }
+        I32x4AllTrue => {
+            let a = lanes_i32(&v128_in(inputs, 0)?);
+            Some(SimdConst::I32(i32::from(a.iter().all(|&x| x != 0))))
+        }
+        I32x4Bitmask => {
+            let a = lanes_i32(&v128_in(inputs, 0)?);
+            Some(SimdConst::I32(
+                a.iter().enumerate().fold(
+                    0,
+                    |mask, (i, &x)| if x < 0 { mask | (1 << i) } else { mask },
+                ),
+            ))
+        }
+        I64x2AllTrue => {
+            let a = lanes_i64(&v128_in(inputs, 0)?);
+            Some(SimdConst::I32(i32::from(a.iter().all(|&x| x != 0))))
+        }
+        I64x2Bitmask => {
+            let a = lanes_i64(&v128_in(inputs, 0)?);
+            Some(SimdConst::I32(
+                a.iter().enumerate().fold(
+                    0,
+                    |mask, (i, &x)| if x < 0 { mask | (1 << i) } else { mask },
+                ),
+            ))
+        }
+
+        I8x16NarrowI16x8S => {
+            let a = lanes_i16(&v128_in(inputs, 0)?);
+            let b = lanes_i16(&v128_in(inputs, 1)?);
+            let sat = |x: i16| x.clamp(i32::from(i8::MIN) as i16, i32::from(i8::MAX) as i16) as i8;
+            Some(SimdConst::V128(from_lanes_i8(core::array::from_fn(|i| {
+                if i < 8 { sat(a[i]) } else { sat(b[i - 8]) }
+            }))))
+        }
+        I8x16NarrowI16x8U => {
+            let a = lanes_i16(&v128_in(inputs, 0)?);
+            let b = lanes_i16(&v128_in(inputs, 1)?);
+            let sat = |x: i16| x.clamp(0, i32::from(u8::MAX) as i16) as u8;
+            Some(SimdConst::V128(from_lanes_u8(core::array::from_fn(|i| {
+                if i < 8 { sat(a[i]) } else { sat(b[i - 8]) }
+            }))))
+        }
+        I16x8NarrowI32x4S => {
+            let a = lanes_i32(&v128_in(inputs, 0)?);
+            let b = lanes_i32(&v128_in(inputs, 1)?);
+            let sat = |x: i32| x.clamp(i32::from(i16::MIN), i32::from(i16::MAX)) as i16;
+            Some(SimdConst::V128(from_lanes_i16(core::array::from_fn(|i| {
+                if i < 4 { sat(a[i]) } else { sat(b[i - 4]) }
+            }))))
+        }
+        I16x8NarrowI32x4U => {
+            let a = lanes_i32(&v128_in(inputs, 0)?);
+            let b = lanes_i32(&v128_in(inputs, 1)?);
+            let sat = |x: i32| x.clamp(0, i32::from(u16::MAX)) as u16;
+            Some(SimdConst::V128(from_lanes_u16(core::array::from_fn(|i| {
+                if i < 4 { sat(a[i]) } else { sat(b[i - 4]) }
+            }))))
+        }
+
+        I16x8ExtendLowI8x16S => {
+            let a = lanes_i8(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_i16(core::array::from_fn(|i| {
+                i16::from(a[i])
+            }))))
+        }
+        I16x8ExtendHighI8x16S => {
+            let a = lanes_i8(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_i16(core::array::from_fn(|i| {
+                i16::from(a[i + 8])
+            }))))
+        }
+        I16x8ExtendLowI8x16U => {
+            let a = lanes_u8(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_u16(core::array::from_fn(|i| {
+                u16::from(a[i])
+            }))))
+        }
+        I16x8ExtendHighI8x16U => {
+            let a = lanes_u8(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_u16(core::array::from_fn(|i| {
+                u16::from(a[i + 8])
+            }))))
+        }
+        I32x4ExtendLowI16x8S => {
+            let a = lanes_i16(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_i32(core::array::from_fn(|i| {
+                i32::from(a[i])
+            }))))
+        }
+        I32x4ExtendHighI16x8S => {
+            let a = lanes_i16(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_i32(core::array::from_fn(|i| {
+                i32::from(a[i + 4])
+            }))))
+        }
+        I32x4ExtendLowI16x8U => {
+            let a = lanes_u16(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_u32(core::array::from_fn(|i| {
+                u32::from(a[i])
+            }))))
+        }
+        I32x4ExtendHighI16x8U => {
+            let a = lanes_u16(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_u32(core::array::from_fn(|i| {
+                u32::from(a[i + 4])
+            }))))
+        }
+        I64x2ExtendLowI32x4S => {
+            let a = lanes_i32(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_i64(core::array::from_fn(|i| {
+                i64::from(a[i])
+            }))))
+        }
+        I64x2ExtendHighI32x4S => {
+            let a = lanes_i32(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_i64(core::array::from_fn(|i| {
+                i64::from(a[i + 2])
+            }))))
+        }
+        I64x2ExtendLowI32x4U => {
+            let a = lanes_u32(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_u64(core::array::from_fn(|i| {
+                u64::from(a[i])
+            }))))
+        }
+        I64x2ExtendHighI32x4U => {
+            let a = lanes_u32(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_u64(core::array::from_fn(|i| {
+                u64::from(a[i + 2])
+            }))))
+        }
+
+        I16x8ExtAddPairwiseI8x16S => {
+            let a = lanes_i8(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_i16(core::array::from_fn(|i| {
+                i16::from(a[2 * i]) + i16::from(a[2 * i + 1])
+            }))))
+        }
+        I16x8ExtAddPairwiseI8x16U => {
+            let a = lanes_u8(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_u16(core::array::from_fn(|i| {
+                u16::from(a[2 * i]) + u16::from(a[2 * i + 1])
+            }))))
+        }
+        I32x4ExtAddPairwiseI16x8S => {
+            let a = lanes_i16(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_i32(core::array::from_fn(|i| {
+                i32::from(a[2 * i]) + i32::from(a[2 * i + 1])
+            }))))
+        }
+        I32x4ExtAddPairwiseI16x8U => {
+            let a = lanes_u16(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_u32(core::array::from_fn(|i| {
+                u32::from(a[2 * i]) + u32::from(a[2 * i + 1])
+            }))))
+        }
+
+        I16x8ExtMulLowI8x16S => {
+            let a = lanes_i8(&v128_in(inputs, 0)?);
+            let b = lanes_i8(&v128_in(inputs, 1)?);
+            Some(SimdConst::V128(from_lanes_i16(core::array::from_fn(|i| {
+                i16::from(a[i]) * i16::from(b[i])
+            }))))
+        }
+        I16x8ExtMulHighI8x16S => {
+            let a = lanes_i8(&v128_in(inputs, 0)?);
+            let b = lanes_i8(&v128_in(inputs, 1)?);
+            Some(SimdConst::V128(from_lanes_i16(core::array::from_fn(|i| {
+                i16::from(a[i + 8]) * i16::from(b[i + 8])
+            }))))
+        }
+        I16x8ExtMulLowI8x16U => {
+            let a = lanes_u8(&v128_in(inputs, 0)?);
+            let b = lanes_u8(&v128_in(inputs, 1)?);
+            Some(SimdConst::V128(from_lanes_u16(core::array::from_fn(|i| {
+                u16::from(a[i]) * u16::from(b[i])
+            }))))
+        }
+        I16x8ExtMulHighI8x16U => {
+            let a = lanes_u8(&v128_in(inputs, 0)?);
+            let b = lanes_u8(&v128_in(inputs, 1)?);
+            Some(SimdConst::V128(from_lanes_u16(core::array::from_fn(|i| {
+                u16::from(a[i + 8]) * u16::from(b[i + 8])
+            }))))
+        }
+        I32x4ExtMulLowI16x8S => {
+            let a = lanes_i16(&v128_in(inputs, 0)?);
+            let b = lanes_i16(&v128_in(inputs, 1)?);
+            Some(SimdConst::V128(from_lanes_i32(core::array::from_fn(|i| {
+                i32::from(a[i]) * i32::from(b[i])
+            }))))
+        }
+        I32x4ExtMulHighI16x8S => {
+            let a = lanes_i16(&v128_in(inputs, 0)?);
+            let b = lanes_i16(&v128_in(inputs, 1)?);
+            Some(SimdConst::V128(from_lanes_i32(core::array::from_fn(|i| {
+                i32::from(a[i + 4]) * i32::from(b[i + 4])
+            }))))
+        }
+        I32x4ExtMulLowI16x8U => {
+            let a = lanes_u16(&v128_in(inputs, 0)?);
+            let b = lanes_u16(&v128_in(inputs, 1)?);
+            Some(SimdConst::V128(from_lanes_u32(core::array::from_fn(|i| {
+                u32::from(a[i]) * u32::from(b[i])
+            }))))
+        }
+        I32x4ExtMulHighI16x8U => {
+            let a = lanes_u16(&v128_in(inputs, 0)?);
+            let b = lanes_u16(&v128_in(inputs, 1)?);
+            Some(SimdConst::V128(from_lanes_u32(core::array::from_fn(|i| {
+                u32::from(a[i + 4]) * u32::from(b[i + 4])
+            }))))
+        }
+        I64x2ExtMulLowI32x4S => {
+            let a = lanes_i32(&v128_in(inputs, 0)?);
+            let b = lanes_i32(&v128_in(inputs, 1)?);
+            Some(SimdConst::V128(from_lanes_i64(core::array::from_fn(|i| {
+                i64::from(a[i]) * i64::from(b[i])
+            }))))
+        }
+        I64x2ExtMulHighI32x4S => {
+            let a = lanes_i32(&v128_in(inputs, 0)?);
+            let b = lanes_i32(&v128_in(inputs, 1)?);
+            Some(SimdConst::V128(from_lanes_i64(core::array::from_fn(|i| {
+                i64::from(a[i + 2]) * i64::from(b[i + 2])
+            }))))
+        }
+        I64x2ExtMulLowI32x4U => {
+            let a = lanes_u32(&v128_in(inputs, 0)?);
+            let b = lanes_u32(&v128_in(inputs, 1)?);
+            Some(SimdConst::V128(from_lanes_u64(core::array::from_fn(|i| {
+                u64::from(a[i]) * u64::from(b[i])
+            }))))
+        }
+        I64x2ExtMulHighI32x4U => {
+            let a = lanes_u32(&v128_in(inputs, 0)?);
+            let b = lanes_u32(&v128_in(inputs, 1)?);
+            Some(SimdConst::V128(from_lanes_u64(core::array::from_fn(|i| {
+                u64::from(a[i + 2]) * u64::from(b[i + 2])
+            }))))
+        }
+
+        I8x16Swizzle => {
+            let a = lanes_u8(&v128_in(inputs, 0)?);
+            let idx = lanes_u8(&v128_in(inputs, 1)?);
+            Some(SimdConst::V128(from_lanes_u8(core::array::from_fn(|i| {
+                let j = idx[i] as usize;
+                if j < 16 { a[j] } else { 0 }
+            }))))
+        }
+
+        SimdOperator::I8x16ExtractLaneS { lane } => {
+            let a = lanes_i8(&v128_in(inputs, 0)?);
+            Some(SimdConst::I32(i32::from(a[*lane as usize % 16])))
+        }
+        SimdOperator::I8x16ExtractLaneU { lane } => {
+            let a = lanes_u8(&v128_in(inputs, 0)?);
+            Some(SimdConst::I32(i32::from(a[*lane as usize % 16])))
+        }
+        SimdOperator::I8x16ReplaceLane { lane } => {
+            let mut a = lanes_i8(&v128_in(inputs, 0)?);
+            a[*lane as usize % 16] = i32_in(inputs, 1)? as i8;
+            Some(SimdConst::V128(from_lanes_i8(a)))
+        }
+        SimdOperator::I16x8ExtractLaneS { lane } => {
+            let a = lanes_i16(&v128_in(inputs, 0)?);
+            Some(SimdConst::I32(i32::from(a[*lane as usize % 8])))
+        }
+        SimdOperator::I16x8ExtractLaneU { lane } => {
+            let a = lanes_u16(&v128_in(inputs, 0)?);
+            Some(SimdConst::I32(i32::from(a[*lane as usize % 8])))
+        }
+        SimdOperator::I16x8ReplaceLane { lane } => {
+            let mut a = lanes_i16(&v128_in(inputs, 0)?);
+            a[*lane as usize % 8] = i32_in(inputs, 1)? as i16;
+            Some(SimdConst::V128(from_lanes_i16(a)))
+        }
+        SimdOperator::I32x4ExtractLane { lane } => {
+            let a = lanes_i32(&v128_in(inputs, 0)?);
+            Some(SimdConst::I32(a[*lane as usize % 4]))
+        }
+        SimdOperator::I32x4ReplaceLane { lane } => {
+            let mut a = lanes_i32(&v128_in(inputs, 0)?);
+            a[*lane as usize % 4] = i32_in(inputs, 1)?;
+            Some(SimdConst::V128(from_lanes_i32(a)))
+        }
+        SimdOperator::I64x2ExtractLane { lane } => {
+            let a = lanes_i64(&v128_in(inputs, 0)?);
+            Some(SimdConst::I64(a[*lane as usize % 2]))
+        }
+        SimdOperator::I64x2ReplaceLane { lane } => {
+            let mut a = lanes_i64(&v128_in(inputs, 0)?);
+            a[*lane as usize % 2] = i64_in(inputs, 1)?;
+            Some(SimdConst::V128(from_lanes_i64(a)))
+        }
+        SimdOperator::F32x4ExtractLane { lane } => {
+            let a = lanes_f32(&v128_in(inputs, 0)?);
+            Some(SimdConst::F32(a[*lane as usize % 4]))
+        }
+        SimdOperator::F32x4ReplaceLane { lane } => {
+            let mut a = lanes_f32(&v128_in(inputs, 0)?);
+            a[*lane as usize % 4] = f32_in(inputs, 1)?;
+            Some(SimdConst::V128(from_lanes_f32(a)))
+        }
+        SimdOperator::F64x2ExtractLane { lane } => {
+            let a = lanes_f64(&v128_in(inputs, 0)?);
+            Some(SimdConst::F64(a[*lane as usize % 2]))
+        }
+        SimdOperator::F64x2ReplaceLane { lane } => {
+            let mut a = lanes_f64(&v128_in(inputs, 0)?);
+            a[*lane as usize % 2] = f64_in(inputs, 1)?;
+            Some(SimdConst::V128(from_lanes_f64(a)))
+        }
+        SimdOperator::I8x16Shuffle { lanes } => {
+            let a = v128_in(inputs, 0)?.bytes();
+            let b = v128_in(inputs, 1)?.bytes();
+            let combined: [u8; 32] = core::array::from_fn(|i| if i < 16 { a[i] } else { b[i - 16] });
+            Some(SimdConst::V128(crate::V128::from(core::array::from_fn(
+                |i| combined[lanes[i] as usize % 32],
+            ))))
+        }
+
+        // Float comparisons: result lanes are i32x4/i64x2-shaped masks.
+        F32x4Eq => fcmp(inputs, lanes_f32, from_lanes_i32, |a: f32, b: f32| a == b),
+        F32x4Ne => fcmp(inputs, lanes_f32, from_lanes_i32, |a: f32, b: f32| a != b),
+        F32x4Lt => fcmp(inputs, lanes_f32, from_lanes_i32, |a: f32, b: f32| a < b),
+        F32x4Gt => fcmp(inputs, lanes_f32, from_lanes_i32, |a: f32, b: f32| a > b),
+        F32x4Le => fcmp(inputs, lanes_f32, from_lanes_i32, |a: f32, b: f32| a <= b),
+        F32x4Ge => fcmp(inputs, lanes_f32, from_lanes_i32, |a: f32, b: f32| a >= b),
+        F64x2Eq => fcmp64(inputs, |a, b| a == b),
+        F64x2Ne => fcmp64(inputs, |a, b| a != b),
+        F64x2Lt => fcmp64(inputs, |a, b| a < b),
+        F64x2Gt => fcmp64(inputs, |a, b| a > b),
+        F64x2Le => fcmp64(inputs, |a, b| a <= b),
+        F64x2Ge => fcmp64(inputs, |a, b| a >= b),
+
+        // -- float <-> int conversions --
+        I32x4TruncSatF32x4S => convop!(inputs, lanes_f32, from_lanes_i32, |a: f32| a as i32),
+        I32x4TruncSatF32x4U => convop!(inputs, lanes_f32, from_lanes_u32, |a: f32| a as u32),
+        F32x4ConvertI32x4S => convop!(inputs, lanes_i32, from_lanes_f32, |a: i32| a as f32),
+        F32x4ConvertI32x4U => convop!(inputs, lanes_u32, from_lanes_f32, |a: u32| a as f32),
+        I32x4TruncSatF64x2SZero => {
+            let a = lanes_f64(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_i32([
+                a[0] as i32,
+                a[1] as i32,
+                0,
+                0,
+            ])))
+        }
+        I32x4TruncSatF64x2UZero => {
+            let a = lanes_f64(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_u32([
+                a[0] as u32,
+                a[1] as u32,
+                0,
+                0,
+            ])))
+        }
+        F64x2ConvertLowI32x4S => {
+            let a = lanes_i32(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_f64([
+                a[0] as f64,
+                a[1] as f64,
+            ])))
+        }
+        F64x2ConvertLowI32x4U => {
+            let a = lanes_u32(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_f64([
+                a[0] as f64,
+                a[1] as f64,
+            ])))
+        }
+        F32x4DemoteF64x2Zero => {
+            let a = lanes_f64(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_f32([
+                a[0] as f32,
+                a[1] as f32,
+                0.0,
+                0.0,
+            ])))
+        }
+        F64x2PromoteLowF32x4 => {
+            let a = lanes_f32(&v128_in(inputs, 0)?);
+            Some(SimdConst::V128(from_lanes_f64([
+                a[0] as f64,
+                a[1] as f64,
+            ])))
+        }
+
+        // Memory/atomic operators have no memory to read, and
+        // `@relaxed_simd` operators are implementation-defined; both, along
+        // with any operator not yet covered above, fold to `None`.
+        _ => None,
+    }
+}
+
+fn fcmp(
+    inputs: &[SimdConst],
+    get: fn(&crate::V128) -> [f32; 4],
+    set: fn([i32; 4]) -> crate::V128,
+    f: impl Fn(f32, f32) -> bool,
+) -> Option<SimdConst> {
+    let a = get(&v128_in(inputs, 0)?);
+    let b = get(&v128_in(inputs, 1)?);
+    Some(SimdConst::V128(set(core::array::from_fn(|i| {
+        if f(a[i], b[i]) { -1 } else { 0 }
+    }))))
+}
+
+fn fcmp64(inputs: &[SimdConst], f: impl Fn(f64, f64) -> bool) -> Option<SimdConst> {
+    let a = lanes_f64(&v128_in(inputs, 0)?);
+    let b = lanes_f64(&v128_in(inputs, 1)?);
+    Some(SimdConst::V128(from_lanes_i64(core::array::from_fn(|i| {
+        if f(a[i], b[i]) { -1 } else { 0 }
+    }))))
+}
@@ -0,0 +1,309 @@
+//! The [`SimdOperator`] enumeration and [`VisitSimdOperator`] trait.
+//!
+//! Both are generated from [`for_each_simd_operator!`] so that adding a new
+//! SIMD opcode to the macro is the only change needed to keep this
+//! enumeration, its visitor trait, and any conversions built on top of them
+//! (such as the `From`/`TryFrom` impls between [`SimdOperator`] and
+//! `wasm_encoder::Instruction` below) in sync.
+
+macro_rules! define_simd_operator {
+    ($( @$proposal:ident $op:ident $({ $($field:ident: $field_ty:ty),* })? => $visit:ident $ann:tt )*) => {
+        /// A SIMD operator independent of its binary encoding.
+        #[derive(Debug, Clone, PartialEq)]
+        #[allow(missing_docs)]
+        pub enum SimdOperator {
+            $(
+                $op $({ $($field: $field_ty),* })?,
+            )*
+        }
+
+        /// A trait for visiting every [`SimdOperator`] variant one method at a
+        /// time, mirroring the shape of [`for_each_simd_operator!`].
+        pub trait VisitSimdOperator<'a> {
+            /// The result type returned by every visitor method.
+            type Output;
+
+            $(
+                #[allow(missing_docs)]
+                fn $visit(&mut self $(, $($field: $field_ty),*)?) -> Self::Output;
+            )*
+
+            /// Dispatches `op` to its corresponding visitor method.
+            fn visit_simd_operator(&mut self, op: SimdOperator) -> Self::Output {
+                match op {
+                    $(
+                        SimdOperator::$op $({ $($field),* })? => self.$visit($($($field),*)?),
+                    )*
+                }
+            }
+        }
+    };
+}
+crate::for_each_simd_operator!(define_simd_operator);
+
+/// Converts a [wasmparser's](crate) [`MemArg`](crate::MemArg) into its
+/// [`wasm_encoder`] counterpart.
+fn encoder_memarg(memarg: crate::MemArg) -> wasm_encoder::MemArg {
+    wasm_encoder::MemArg {
+        offset: memarg.offset,
+        align: u32::from(memarg.align),
+        memory_index: memarg.memory,
+    }
+}
+
+/// Converts a [`wasm_encoder::MemArg`] back into [wasmparser's](crate)
+/// [`MemArg`](crate::MemArg).
+fn parser_memarg(memarg: wasm_encoder::MemArg) -> crate::MemArg {
+    crate::MemArg {
+        offset: memarg.offset,
+        align: memarg.align as u8,
+        max_align: memarg.align as u8,
+        memory: memarg.memory_index,
+    }
+}
+
+macro_rules! simd_operator_to_instruction_arm {
+    ($op:ident) => {
+        wasm_encoder::Instruction::$op
+    };
+    ($op:ident memarg) => {
+        wasm_encoder::Instruction::$op(encoder_memarg(memarg))
+    };
+    ($op:ident memarg, lane) => {
+        wasm_encoder::Instruction::$op(encoder_memarg(memarg), lane)
+    };
+    ($op:ident lane) => {
+        wasm_encoder::Instruction::$op(lane)
+    };
+    ($op:ident lanes) => {
+        wasm_encoder::Instruction::$op(lanes)
+    };
+    ($op:ident value) => {
+        wasm_encoder::Instruction::$op(value.i128())
+    };
+}
+
+macro_rules! instruction_to_simd_operator_arm {
+    ($op:ident) => {
+        SimdOperator::$op
+    };
+    ($op:ident memarg) => {
+        SimdOperator::$op {
+            memarg: parser_memarg(memarg),
+        }
+    };
+    ($op:ident memarg, lane) => {
+        SimdOperator::$op {
+            memarg: parser_memarg(memarg),
+            lane,
+        }
+    };
+    ($op:ident lane) => {
+        SimdOperator::$op { lane }
+    };
+    ($op:ident lanes) => {
+        SimdOperator::$op { lanes }
+    };
+    ($op:ident value) => {
+        SimdOperator::$op {
+            value: crate::V128::from(value),
+        }
+    };
+}
+
+macro_rules! define_simd_instruction_conversions {
+    ($( @$proposal:ident $op:ident $({ $($field:ident: $field_ty:ty),* })? => $visit:ident $ann:tt )*) => {
+        impl From<SimdOperator> for wasm_encoder::Instruction<'static> {
+            fn from(op: SimdOperator) -> Self {
+                match op {
+                    $(
+                        SimdOperator::$op $({ $($field),* })? => {
+                            simd_operator_to_instruction_arm!($op $($($field),*)?)
+                        }
+                    )*
+                }
+            }
+        }
+
+        impl<'a> TryFrom<wasm_encoder::Instruction<'a>> for SimdOperator {
+            type Error = ();
+
+            fn try_from(inst: wasm_encoder::Instruction<'a>) -> Result<Self, Self::Error> {
+                Ok(match inst {
+                    $(
+                        wasm_encoder::Instruction::$op $(($($field),*))? => {
+                            instruction_to_simd_operator_arm!($op $($($field),*)?)
+                        }
+                    )*
+                    _ => return Err(()),
+                })
+            }
+        }
+    };
+}
+crate::for_each_simd_operator!(define_simd_instruction_conversions);
+
+use alloc::vec::Vec;
+
+/// A WebAssembly value type appearing in a [`SimdOperatorSignature`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum SimdValType {
+    I32,
+    I64,
+    F32,
+    F64,
+    V128,
+}
+
+/// The operand and result types of a [`SimdOperator`], derived from its
+/// annotation in [`for_each_simd_operator!`].
+///
+/// Lane-index immediates (as used by extract-lane, replace-lane, and
+/// shuffle operators) are *not* included in `inputs`: they are encoded
+/// directly in the instruction rather than popped from the value stack.
+/// Shift amounts and splat operands, by contrast, are genuine stack operands
+/// and do appear in `inputs`.
+///
+/// [`for_each_simd_operator!`]: crate::for_each_simd_operator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimdOperatorSignature {
+    /// The value types popped off the stack, deepest operand first.
+    pub inputs: Vec<SimdValType>,
+    /// The value types pushed onto the stack.
+    pub outputs: Vec<SimdValType>,
+}
+
+impl SimdOperatorSignature {
+    /// The number of stack operands this operator pops.
+    pub fn arity(&self) -> usize {
+        self.inputs.len()
+    }
+}
+
+macro_rules! simd_val_type {
+    (i32) => {
+        SimdValType::I32
+    };
+    (i64) => {
+        SimdValType::I64
+    };
+    (f32) => {
+        SimdValType::F32
+    };
+    (f64) => {
+        SimdValType::F64
+    };
+    (v128) => {
+        SimdValType::V128
+    };
+    (v128f) => {
+        SimdValType::V128
+    };
+}
+
+macro_rules! simd_operator_signature {
+    (load $ty:ident) => {
+        SimdOperatorSignature {
+            inputs: alloc::vec![SimdValType::I32],
+            outputs: alloc::vec![simd_val_type!($ty)],
+        }
+    };
+    (load lane $n:literal) => {
+        SimdOperatorSignature {
+            inputs: alloc::vec![SimdValType::I32, SimdValType::V128],
+            outputs: alloc::vec![SimdValType::V128],
+        }
+    };
+    (store $ty:ident) => {
+        SimdOperatorSignature {
+            inputs: alloc::vec![SimdValType::I32, simd_val_type!($ty)],
+            outputs: alloc::vec![],
+        }
+    };
+    (store lane $n:literal) => {
+        SimdOperatorSignature {
+            inputs: alloc::vec![SimdValType::I32, SimdValType::V128],
+            outputs: alloc::vec![],
+        }
+    };
+    (push $ty:ident) => {
+        SimdOperatorSignature {
+            inputs: alloc::vec![],
+            outputs: alloc::vec![simd_val_type!($ty)],
+        }
+    };
+    (arity $n_in:literal -> $n_out:literal) => {
+        SimdOperatorSignature {
+            inputs: alloc::vec![SimdValType::V128; $n_in],
+            outputs: alloc::vec![SimdValType::V128; $n_out],
+        }
+    };
+    (extract $ty:ident $lanes:literal) => {
+        SimdOperatorSignature {
+            inputs: alloc::vec![SimdValType::V128],
+            outputs: alloc::vec![simd_val_type!($ty)],
+        }
+    };
+    (replace $ty:ident $lanes:literal) => {
+        SimdOperatorSignature {
+            inputs: alloc::vec![SimdValType::V128, simd_val_type!($ty)],
+            outputs: alloc::vec![SimdValType::V128],
+        }
+    };
+    (binary $ty:ident) => {
+        SimdOperatorSignature {
+            inputs: alloc::vec![SimdValType::V128, SimdValType::V128],
+            outputs: alloc::vec![SimdValType::V128],
+        }
+    };
+    (unary $ty:ident) => {
+        SimdOperatorSignature {
+            inputs: alloc::vec![SimdValType::V128],
+            outputs: alloc::vec![SimdValType::V128],
+        }
+    };
+    (ternary $ty:ident) => {
+        SimdOperatorSignature {
+            inputs: alloc::vec![SimdValType::V128, SimdValType::V128, SimdValType::V128],
+            outputs: alloc::vec![SimdValType::V128],
+        }
+    };
+    (test $ty:ident) => {
+        SimdOperatorSignature {
+            inputs: alloc::vec![SimdValType::V128],
+            outputs: alloc::vec![SimdValType::I32],
+        }
+    };
+    (splat $ty:ident) => {
+        SimdOperatorSignature {
+            inputs: alloc::vec![simd_val_type!($ty)],
+            outputs: alloc::vec![SimdValType::V128],
+        }
+    };
+    (shift $ty:ident) => {
+        SimdOperatorSignature {
+            inputs: alloc::vec![SimdValType::V128, SimdValType::I32],
+            outputs: alloc::vec![SimdValType::V128],
+        }
+    };
+}
+
+macro_rules! define_simd_operator_signatures {
+    ($( @$proposal:ident $op:ident $({ $($field:ident: $field_ty:ty),* })? => $visit:ident ($($ann:tt)*) )*) => {
+        impl SimdOperator {
+            /// Returns this operator's operand and result types, derived
+            /// from its [`for_each_simd_operator!`] annotation.
+            ///
+            /// [`for_each_simd_operator!`]: crate::for_each_simd_operator
+            pub fn signature(&self) -> SimdOperatorSignature {
+                match self {
+                    $(
+                        SimdOperator::$op $({ $($field: _),* })? => simd_operator_signature!($($ann)*),
+                    )*
+                }
+            }
+        }
+    };
+}
+crate::for_each_simd_operator!(define_simd_operator_signatures);